@@ -0,0 +1,194 @@
+use crate::{HttpMethod, RecordedRequest, RequestTemplate};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use tokio::fs;
+
+/// Format of the time of the nginx and Apache common and combined log formats.
+const CLF_TIME_FORMAT: &str = "%d/%b/%Y:%H:%M:%S %z";
+
+/// JSON representation of a line of a JSON Lines access log.
+#[derive(Debug, Deserialize)]
+struct AccessLogLine {
+    #[serde(alias = "timestamp")]
+    time: String,
+    method: Option<String>,
+    path: String,
+}
+
+/// The requests of production traffic that is recorded in an access log.
+#[derive(Debug, Clone)]
+pub struct AccessLog {
+    requests: Vec<RecordedRequest>,
+}
+
+impl AccessLog {
+    /// Reads an access log.
+    ///
+    /// Every line is either in the nginx and Apache common or combined log format, e.g.
+    /// `127.0.0.1 - - [10/Oct/2025:13:55:36 +0000] "GET /items?page=1 HTTP/1.1" 200 512`, or a
+    /// JSON object with a `time` (RFC 3339), an optional `method` and a `path`, e.g.
+    /// `{"time": "2025-10-10T13:55:36Z", "method": "GET", "path": "/items?page=1"}`. Requests with
+    /// an HTTP method that is not supported, e.g. `OPTIONS`, are skipped.
+    ///
+    /// # Parameters
+    ///
+    /// * `path`: Access log file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `AccessLog` instance if successful.
+    pub async fn from_file(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            bail!(
+                "Access log '{}' does not exist or is not a file",
+                path.display()
+            );
+        }
+        let content = fs::read_to_string(path).await?;
+        let log =
+            Self::parse(&content).context(format!("Invalid access log '{}'", path.display()))?;
+        if log.is_empty() {
+            bail!(
+                "Access log '{}' does not contain any requests",
+                path.display()
+            );
+        }
+        Ok(log)
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let mut requests = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (time, method, path) =
+                Self::parse_line(line).context(format!("Invalid request on line {}", i + 1))?;
+            if HttpMethod::from_str(&method).is_err() {
+                continue;
+            }
+            requests.push((time, Self::to_template(method, path)));
+        }
+        // The lines of an access log are written when the responses complete, so they are not
+        // necessarily in the order of the requests.
+        requests.sort_by_key(|(time, _)| *time);
+        let first = requests.first().map(|(time, _)| *time);
+        let requests = requests
+            .into_iter()
+            .map(|(time, request)| RecordedRequest {
+                request,
+                offset: first
+                    .and_then(|first| (time - first).to_std().ok())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        Ok(AccessLog { requests })
+    }
+
+    fn parse_line(line: &str) -> Result<(DateTime<FixedOffset>, String, String)> {
+        if line.starts_with('{') {
+            let line: AccessLogLine = serde_json::from_str(line)?;
+            let time = DateTime::parse_from_rfc3339(&line.time)
+                .context(format!("Invalid time '{}'", line.time))?;
+            let method = line.method.unwrap_or_else(|| "GET".to_owned());
+            return Ok((time, method, line.path));
+        }
+        let (time, rest) = line
+            .split_once('[')
+            .and_then(|(_, rest)| rest.split_once(']'))
+            .context("Missing time")?;
+        let time = DateTime::parse_from_str(time, CLF_TIME_FORMAT)
+            .context(format!("Invalid time '{time}'"))?;
+        let request = rest
+            .split_once('"')
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(request, _)| request)
+            .context("Missing request")?;
+        let mut parts = request.split_whitespace();
+        let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+            bail!("Invalid request '{request}'");
+        };
+        Ok((time, method.to_owned(), path.to_owned()))
+    }
+
+    fn to_template(method: String, path: String) -> RequestTemplate {
+        RequestTemplate {
+            name: None,
+            weight: None,
+            method: Some(method),
+            url: None,
+            path: Some(path),
+            query: BTreeMap::new(),
+            headers: BTreeMap::new(),
+            body: None,
+            binary_body: None,
+            form: BTreeMap::new(),
+            form_urlencoded: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the requests of the access log, ordered by their time.
+    pub fn requests(&self) -> &[RecordedRequest] {
+        &self.requests
+    }
+
+    /// Returns the number of requests.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns `true` if there are no requests.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn parse_succeeds() {
+        let log = AccessLog::parse(
+            r#"10.0.0.1 - - [10/Oct/2025:13:55:36 +0000] "GET /items?page=1 HTTP/1.1" 200 512 "-" "curl/8.0"
+10.0.0.2 - frank [10/Oct/2025:15:55:35 +0200] "HEAD /health HTTP/1.1" 200 0
+10.0.0.3 - - [10/Oct/2025:13:55:38 +0000] "OPTIONS /items HTTP/1.1" 204 0
+
+{"time": "2025-10-10T13:55:37.500Z", "path": "/search?q=red"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(log.len(), 3);
+        let requests = log.requests();
+        assert_eq!(requests[0].offset, Duration::ZERO);
+        assert_eq!(requests[0].request.method.as_deref(), Some("HEAD"));
+        assert_eq!(requests[0].request.path.as_deref(), Some("/health"));
+        assert_eq!(requests[1].offset, Duration::from_secs(1));
+        assert_eq!(requests[1].request.path.as_deref(), Some("/items?page=1"));
+        assert_eq!(requests[2].offset, Duration::from_millis(2500));
+        assert_eq!(requests[2].request.method.as_deref(), Some("GET"));
+        assert_eq!(requests[2].request.path.as_deref(), Some("/search?q=red"));
+    }
+
+    #[test]
+    fn parse_invalid_line_fails() {
+        let err = AccessLog::parse(
+            r#"10.0.0.1 - - [10/Oct/2025:13:55:36 +0000] "GET /items HTTP/1.1" 200 512
+10.0.0.1 - - [yesterday] "GET /items HTTP/1.1" 200 512"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid request on line 2");
+        assert_eq!(
+            err.chain().nth(1).unwrap().to_string(),
+            "Invalid time 'yesterday'"
+        );
+        assert!(AccessLog::parse(r#"{"path": "/items"}"#).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{Result, bail};
+use bytes::Bytes;
+use flate2::write::GzEncoder;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Quality of the Brotli compression between 0 and 11, traded off against its speed.
+const BROTLI_QUALITY: u32 = 5;
+
+/// Base-2 logarithm of the Brotli window size.
+const BROTLI_WINDOW: u32 = 22;
+
+/// Compression of request bodies, which is announced with the `Content-Encoding` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyCompression {
+    /// `gzip` compression.
+    Gzip,
+
+    /// `br` (Brotli) compression.
+    Brotli,
+
+    /// `zstd` (Zstandard) compression.
+    Zstd,
+}
+
+impl FromStr for BodyCompression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "gzip" => Ok(BodyCompression::Gzip),
+            "br" => Ok(BodyCompression::Brotli),
+            "zstd" => Ok(BodyCompression::Zstd),
+            _ => bail!("'{s}' is not a valid body compression"),
+        }
+    }
+}
+
+impl BodyCompression {
+    /// Returns the value of the `Content-Encoding` header.
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            BodyCompression::Gzip => "gzip",
+            BodyCompression::Brotli => "br",
+            BodyCompression::Zstd => "zstd",
+        }
+    }
+
+    /// Compresses a body with the default level of the compression.
+    ///
+    /// # Parameters
+    ///
+    /// * `data`: The body to compress.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the compressed body if successful.
+    pub fn compress(&self, data: &[u8]) -> Result<Bytes> {
+        let compressed = match self {
+            BodyCompression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+            BodyCompression::Brotli => {
+                let mut compressed = Vec::new();
+                {
+                    let mut writer = brotli::CompressorWriter::new(
+                        &mut compressed,
+                        4096,
+                        BROTLI_QUALITY,
+                        BROTLI_WINDOW,
+                    );
+                    writer.write_all(data)?;
+                }
+                compressed
+            }
+            BodyCompression::Zstd => zstd::bulk::compress(data, zstd::DEFAULT_COMPRESSION_LEVEL)?,
+        };
+        Ok(compressed.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    const DATA: &str = r#"{"message": "hello", "items": [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]}"#;
+
+    #[test]
+    fn parse_succeeds() {
+        assert_eq!(
+            BodyCompression::from_str("GZIP").unwrap(),
+            BodyCompression::Gzip
+        );
+        assert_eq!(
+            BodyCompression::from_str("br").unwrap().content_encoding(),
+            "br"
+        );
+        assert_eq!(
+            BodyCompression::from_str("deflate")
+                .unwrap_err()
+                .to_string(),
+            "'deflate' is not a valid body compression"
+        );
+    }
+
+    #[test]
+    fn compress_gzip_succeeds() {
+        let compressed = BodyCompression::Gzip.compress(DATA.as_bytes()).unwrap();
+        let mut decompressed = String::new();
+        GzDecoder::new(&compressed[..])
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, DATA);
+    }
+
+    #[test]
+    fn compress_brotli_succeeds() {
+        let compressed = BodyCompression::Brotli.compress(DATA.as_bytes()).unwrap();
+        let mut decompressed = String::new();
+        brotli::Decompressor::new(&compressed[..], 4096)
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, DATA);
+    }
+
+    #[test]
+    fn compress_zstd_succeeds() {
+        let compressed = BodyCompression::Zstd.compress(DATA.as_bytes()).unwrap();
+        let decompressed = zstd::decode_all(&compressed[..]).unwrap();
+        assert_eq!(decompressed, DATA.as_bytes());
+    }
+}
@@ -0,0 +1,499 @@
+use crate::{HttpMethod, RequestTemplate};
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::{Chars, FromStr};
+use tokio::fs;
+
+/// Short options of curl that take a value.
+const SHORT_OPTIONS_WITH_VALUE: &str = "XHdubAeEmo";
+
+/// A request that is parsed from a curl command line, e.g. one copied from the browser devtools.
+#[derive(Debug, Clone)]
+pub struct CurlCommand {
+    pub(crate) request: RequestTemplate,
+
+    /// Whether TLS certificate verification is skipped (`-k`).
+    pub insecure: bool,
+
+    /// Custom CA certificate file (`--cacert`).
+    pub ca_cert: Option<PathBuf>,
+
+    /// Public certificate file (`--cert`).
+    pub cert: Option<PathBuf>,
+
+    /// Private key file (`--key`), which defaults to the public certificate file.
+    pub key: Option<PathBuf>,
+}
+
+impl CurlCommand {
+    /// Reads a file of curl commands, where every command starts on a new line and may continue
+    /// over several lines that end with a backslash. Lines that start with `#` are ignored.
+    ///
+    /// # Parameters
+    ///
+    /// * `path`: File of curl commands.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `CurlCommand` instances if successful.
+    pub async fn from_file(path: &Path) -> Result<Vec<Self>> {
+        if !path.is_file() {
+            bail!(
+                "Curl file '{}' does not exist or is not a file",
+                path.display()
+            );
+        }
+        let content = fs::read_to_string(path).await?;
+        let commands = Self::parse_all(&content)
+            .await
+            .context(format!("Invalid curl file '{}'", path.display()))?;
+        if commands.is_empty() {
+            bail!(
+                "Curl file '{}' does not contain any commands",
+                path.display()
+            );
+        }
+        Ok(commands)
+    }
+
+    /// Parses one or more curl commands.
+    ///
+    /// The supported options are `-X`, `-H`, `-d`, `--data-raw`, `--data-binary`,
+    /// `--data-urlencode`, `-G`, `-I`, `-u`, `-b`, `-A`, `-e`, `--url`, `-k`, `--cacert`, `--cert`
+    /// and `--key`. Options that do not change the request, e.g. `--compressed`, `-s` or `-L`,
+    /// are ignored.
+    ///
+    /// # Parameters
+    ///
+    /// * `input`: Curl commands.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `CurlCommand` instances if successful.
+    pub async fn parse_all(input: &str) -> Result<Vec<Self>> {
+        let mut commands = Vec::new();
+        for (i, words) in split_commands(input)?.into_iter().enumerate() {
+            let command = Self::parse_words(&words)
+                .await
+                .context(format!("Invalid curl command {}", i + 1))?;
+            commands.push(command);
+        }
+        Ok(commands)
+    }
+
+    async fn parse_words(words: &[String]) -> Result<Self> {
+        let Some((curl, args)) = words.split_first() else {
+            bail!("Empty curl command");
+        };
+        if curl != "curl" {
+            bail!("Expected 'curl' but got '{curl}'");
+        }
+        let mut method = None;
+        let mut url = None;
+        let mut headers = BTreeMap::new();
+        let mut data: Vec<Vec<u8>> = Vec::new();
+        let mut get = false;
+        let mut command = CurlCommand {
+            request: RequestTemplate::default(),
+            insecure: false,
+            ca_cert: None,
+            cert: None,
+            key: None,
+        };
+        let args = expand_short_options(args);
+        let mut args = args.into_iter();
+        while let Some(option) = args.next() {
+            if !option.starts_with('-') {
+                url = Some(option);
+                continue;
+            }
+            let value = if takes_value(&option) {
+                args.next()
+                    .context(format!("Missing value of option '{option}'"))?
+            } else {
+                String::new()
+            };
+            match option.as_str() {
+                "-X" | "--request" => method = Some(value.to_ascii_uppercase()),
+                "-H" | "--header" => {
+                    let (name, value) = value
+                        .split_once(':')
+                        .context(format!("Invalid header '{value}'"))?;
+                    headers.insert(name.trim().to_owned(), value.trim().to_owned());
+                }
+                "-d" | "--data" | "--data-ascii" => {
+                    if let Some(path) = value.strip_prefix('@') {
+                        let mut content = read_file(path).await?;
+                        content.retain(|b| *b != b'\r' && *b != b'\n');
+                        data.push(content);
+                    } else {
+                        data.push(value.into_bytes());
+                    }
+                }
+                "--data-raw" => data.push(value.into_bytes()),
+                "--data-binary" => match value.strip_prefix('@') {
+                    Some(path) => data.push(read_file(path).await?),
+                    None => data.push(value.into_bytes()),
+                },
+                "--data-urlencode" => data.push(url_encode_data(&value)?.into_bytes()),
+                "-G" | "--get" => get = true,
+                "-I" | "--head" => method = Some("HEAD".to_owned()),
+                "-u" | "--user" => {
+                    let credentials = if value.contains(':') {
+                        value
+                    } else {
+                        format!("{value}:")
+                    };
+                    headers.insert(
+                        "Authorization".to_owned(),
+                        format!("Basic {}", BASE64_STANDARD.encode(credentials)),
+                    );
+                }
+                "-b" | "--cookie" => {
+                    if !value.contains('=') {
+                        bail!("Cookie files are not supported");
+                    }
+                    headers.insert("Cookie".to_owned(), value);
+                }
+                "-A" | "--user-agent" => {
+                    headers.insert("User-Agent".to_owned(), value);
+                }
+                "-e" | "--referer" => {
+                    headers.insert("Referer".to_owned(), value);
+                }
+                "--url" => url = Some(value),
+                "-k" | "--insecure" => command.insecure = true,
+                "--cacert" => command.ca_cert = Some(value.into()),
+                "-E" | "--cert" => command.cert = Some(value.into()),
+                "--key" => command.key = Some(value.into()),
+                "--compressed" | "-s" | "--silent" | "-S" | "--show-error" | "-L"
+                | "--location" | "-i" | "--include" | "-v" | "--verbose" | "-f" | "--fail"
+                | "--http1.1" | "--http2" | "-m" | "--max-time" | "--connect-timeout" | "-o"
+                | "--output" => {}
+                _ => bail!("Unsupported curl option '{option}'"),
+            }
+        }
+        let mut url = url.context("Missing URL")?;
+        if !url.contains("://") {
+            url = format!("http://{url}");
+        }
+        let data = (!data.is_empty()).then(|| data.join(&b'&'));
+        let mut body = None;
+        if let Some(data) = data {
+            if get {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                url = format!("{url}{separator}{}", String::from_utf8(data)?);
+            } else {
+                if !headers
+                    .keys()
+                    .any(|name| name.eq_ignore_ascii_case("content-type"))
+                {
+                    headers.insert(
+                        "Content-Type".to_owned(),
+                        "application/x-www-form-urlencoded".to_owned(),
+                    );
+                }
+                method.get_or_insert_with(|| "POST".to_owned());
+                body = Some(data);
+            }
+        }
+        if let Some(method) = &method {
+            HttpMethod::from_str(method)?;
+        }
+        if command.key.is_none() {
+            command.key = command.cert.clone();
+        }
+        command.request.method = method;
+        command.request.url = Some(url);
+        command.request.headers = headers;
+        match body.map(String::from_utf8) {
+            Some(Ok(body)) => command.request.body = Some(body),
+            Some(Err(e)) => {
+                command.request.binary_body = Some(BASE64_STANDARD.encode(e.into_bytes()))
+            }
+            None => {}
+        }
+        Ok(command)
+    }
+
+    /// Returns the URL of the request.
+    pub fn url(&self) -> &str {
+        self.request.url.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the request as a line of a request manifest.
+    pub fn to_manifest_line(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.request)?)
+    }
+}
+
+/// Expands combined short options, e.g. `-sSL` into `-s -S -L`, and short options with an
+/// attached value, e.g. `-XPOST` into `-X POST`.
+fn expand_short_options(args: &[String]) -> Vec<String> {
+    let mut expanded = Vec::new();
+    for arg in args {
+        let Some(options) = arg
+            .strip_prefix('-')
+            .filter(|o| o.len() > 1 && !o.starts_with('-'))
+        else {
+            expanded.push(arg.clone());
+            continue;
+        };
+        for (i, option) in options.char_indices() {
+            expanded.push(format!("-{option}"));
+            if SHORT_OPTIONS_WITH_VALUE.contains(option) {
+                let value = &options[i + option.len_utf8()..];
+                if !value.is_empty() {
+                    expanded.push(value.to_owned());
+                }
+                break;
+            }
+        }
+    }
+    expanded
+}
+
+fn takes_value(option: &str) -> bool {
+    match option.strip_prefix("--") {
+        Some(long) => matches!(
+            long,
+            "request"
+                | "header"
+                | "data"
+                | "data-ascii"
+                | "data-raw"
+                | "data-binary"
+                | "data-urlencode"
+                | "user"
+                | "cookie"
+                | "user-agent"
+                | "referer"
+                | "url"
+                | "cacert"
+                | "cert"
+                | "key"
+                | "max-time"
+                | "connect-timeout"
+                | "output"
+        ),
+        None => option
+            .strip_prefix('-')
+            .is_some_and(|short| SHORT_OPTIONS_WITH_VALUE.contains(short)),
+    }
+}
+
+async fn read_file(path: &str) -> Result<Vec<u8>> {
+    fs::read(path)
+        .await
+        .context(format!("Failed to read data file '{path}'"))
+}
+
+/// URL-encodes the value of `--data-urlencode`, which is either `content`, `=content` or
+/// `name=content`, where only the content is encoded.
+fn url_encode_data(value: &str) -> Result<String> {
+    match value.split_once('=') {
+        Some(("", content)) => Ok(url_encode(content)),
+        Some((name, content)) => Ok(format!("{name}={}", url_encode(content))),
+        None if value.contains('@') => {
+            bail!("Reading --data-urlencode from a file is not supported")
+        }
+        None => Ok(url_encode(value)),
+    }
+}
+
+fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Splits shell command lines into their words, following the quoting rules of POSIX shells and
+/// the `$'...'` quotes of Bash. An unquoted newline ends a command, unless it is escaped with a
+/// backslash.
+fn split_commands(input: &str) -> Result<Vec<Vec<String>>> {
+    let mut commands = Vec::new();
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                words.extend(word.take());
+                if !words.is_empty() {
+                    commands.push(std::mem::take(&mut words));
+                }
+            }
+            c if c.is_whitespace() => words.extend(word.take()),
+            '#' if word.is_none() => while chars.next_if(|c| *c != '\n').is_some() {},
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('\r') if chars.next_if_eq(&'\n').is_some() => {}
+                Some(c) => word.get_or_insert_default().push(c),
+                None => bail!("Unexpected end of input after '\\'"),
+            },
+            '\'' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => bail!("Unclosed single quote"),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => bail!("Unclosed double quote"),
+                        },
+                        Some(c) => word.push(c),
+                        None => bail!("Unclosed double quote"),
+                    }
+                }
+            }
+            '$' if chars.next_if_eq(&'\'').is_some() => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => word.push(unescape_ansi_c(&mut chars)?),
+                        Some(c) => word.push(c),
+                        None => bail!("Unclosed $' quote"),
+                    }
+                }
+            }
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+    if !words.is_empty() {
+        commands.push(words);
+    }
+    Ok(commands)
+}
+
+/// Unescapes a backslash escape sequence of a `$'...'` quote.
+fn unescape_ansi_c(chars: &mut Peekable<Chars<'_>>) -> Result<char> {
+    Ok(match chars.next() {
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('t') => '\t',
+        Some('x') => unescape_hex(chars, 2)?,
+        Some('u') => unescape_hex(chars, 4)?,
+        Some('U') => unescape_hex(chars, 8)?,
+        Some(c) => c,
+        None => bail!("Unclosed $' quote"),
+    })
+}
+
+fn unescape_hex(chars: &mut Peekable<Chars<'_>>, digits: usize) -> Result<char> {
+    let mut code = String::new();
+    while code.len() < digits
+        && let Some(c) = chars.next_if(char::is_ascii_hexdigit)
+    {
+        code.push(c);
+    }
+    u32::from_str_radix(&code, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .context(format!("Invalid escape sequence '{code}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_commands_succeeds() {
+        let commands = split_commands(
+            "# Copied from the devtools\ncurl 'https://example.com/a b' \\\n  -H \"X-Say: \\\"hi\\\"\" \\\n  --data-raw $'{\"a\":\\n1}'\ncurl example.com -d a\\ b\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            commands,
+            vec![
+                vec![
+                    "curl",
+                    "https://example.com/a b",
+                    "-H",
+                    "X-Say: \"hi\"",
+                    "--data-raw",
+                    "{\"a\":\n1}"
+                ],
+                vec!["curl", "example.com", "-d", "a b"],
+            ]
+        );
+        assert!(split_commands("curl 'example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn parse_all_succeeds() {
+        let commands = CurlCommand::parse_all(
+            r#"curl 'https://api.example.com/orders' -H 'Content-Type: application/json' -H 'Authorization: Bearer abc' --data-raw '{"sku":"42"}' --compressed
+curl -sS -XPUT example.com/items/1 -u alice:secret -d a=1 -d b=2 -k --cacert ca.pem --cert client.pem
+curl -G https://example.com/search --data-urlencode 'q=red shoes'"#,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(commands.len(), 3);
+        assert_eq!(
+            commands[0].to_manifest_line().unwrap(),
+            r#"{"method":"POST","url":"https://api.example.com/orders","headers":{"Authorization":"Bearer abc","Content-Type":"application/json"},"body":"{\"sku\":\"42\"}"}"#
+        );
+        assert_eq!(
+            commands[1].to_manifest_line().unwrap(),
+            r#"{"method":"PUT","url":"http://example.com/items/1","headers":{"Authorization":"Basic YWxpY2U6c2VjcmV0","Content-Type":"application/x-www-form-urlencoded"},"body":"a=1&b=2"}"#
+        );
+        assert!(commands[1].insecure);
+        assert_eq!(commands[1].ca_cert, Some(PathBuf::from("ca.pem")));
+        assert_eq!(commands[1].key, Some(PathBuf::from("client.pem")));
+        assert_eq!(
+            commands[2].to_manifest_line().unwrap(),
+            r#"{"url":"https://example.com/search?q=red%20shoes"}"#
+        );
+        assert_eq!(
+            commands[2].url(),
+            "https://example.com/search?q=red%20shoes"
+        );
+    }
+
+    #[tokio::test]
+    async fn parse_invalid_command_fails() {
+        let err = CurlCommand::parse_all("curl example.com\ncurl --proxy x example.com")
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid curl command 2");
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Unsupported curl option '--proxy'"
+        );
+        assert!(CurlCommand::parse_all("wget example.com").await.is_err());
+        assert!(
+            CurlCommand::parse_all("curl -X OPTIONS example.com")
+                .await
+                .is_err()
+        );
+        assert!(CurlCommand::parse_all("curl -H").await.is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use crate::Order;
+use anyhow::{Result, bail};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+/// Specifies how items are picked from a [`DataSource`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickMode {
+    /// Pick items in order and fail once all items have been picked.
+    Sequential,
+
+    /// Pick a random item for every request. Items may be picked more than once.
+    Random,
+
+    /// Pick every item once in a random order and fail once all items have been picked.
+    Unique,
+
+    /// Pick items in order and start over once all items have been picked.
+    Circular,
+
+    /// Pick a random item for every request in proportion to its weight.
+    Weighted,
+}
+
+impl From<Order> for PickMode {
+    fn from(order: Order) -> Self {
+        match order {
+            Order::Sequential => PickMode::Circular,
+            Order::Random => PickMode::Random,
+            Order::Weighted => PickMode::Weighted,
+        }
+    }
+}
+
+/// A list of items, such as request bodies, manifest entries or feeder rows, that are picked by
+/// the iteration number of a request.
+#[derive(Debug, Clone)]
+pub struct DataSource<T> {
+    items: Vec<T>,
+    mode: PickMode,
+    permutation: Vec<usize>,
+    cumulative_weights: Vec<u64>,
+}
+
+impl<T> DataSource<T> {
+    /// Creates a new `DataSource`.
+    ///
+    /// # Parameters
+    ///
+    /// * `items`: Items to pick from.
+    /// * `mode`: How to pick the items.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `DataSource` instance if successful.
+    pub fn new(items: Vec<T>, mode: PickMode) -> Result<Self> {
+        let weights = vec![1; items.len()];
+        Self::with_weights(items, weights, mode)
+    }
+
+    /// Creates a new `DataSource` with a weight per item. The weights are only used by
+    /// [`PickMode::Weighted`].
+    ///
+    /// # Parameters
+    ///
+    /// * `items`: Items to pick from.
+    /// * `weights`: Weight of every item.
+    /// * `mode`: How to pick the items.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `DataSource` instance if successful.
+    pub fn with_weights(items: Vec<T>, weights: Vec<u32>, mode: PickMode) -> Result<Self> {
+        if items.is_empty() {
+            bail!("Data source cannot be empty");
+        }
+        if items.len() != weights.len() {
+            bail!(
+                "Expected {} weight(s) but got {}",
+                items.len(),
+                weights.len()
+            );
+        }
+        let mut permutation = Vec::new();
+        if mode == PickMode::Unique {
+            permutation = (0..items.len()).collect();
+            permutation.shuffle(&mut rand::rng());
+        }
+        let mut cumulative_weights = Vec::new();
+        if mode == PickMode::Weighted {
+            cumulative_weights = weights
+                .iter()
+                .scan(0u64, |total, weight| {
+                    *total += *weight as u64;
+                    Some(*total)
+                })
+                .collect();
+            if cumulative_weights.last() == Some(&0) {
+                bail!("Sum of weights must be greater than zero");
+            }
+        }
+        Ok(DataSource {
+            items,
+            mode,
+            permutation,
+            cumulative_weights,
+        })
+    }
+
+    /// Returns the number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if there are no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns all items.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Picks the item of the specified iteration.
+    ///
+    /// # Parameters
+    ///
+    /// * `iteration`: Iteration number of the request, starting at 0.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the picked item, or an error if all items have been picked.
+    pub fn pick(&self, iteration: u64) -> Result<&T> {
+        let len = self.items.len();
+        let index = match self.mode {
+            PickMode::Circular => iteration as usize % len,
+            PickMode::Random => rand::rng().random_range(0..len),
+            PickMode::Weighted => {
+                let total = self.cumulative_weights[len - 1];
+                let value = rand::rng().random_range(0..total);
+                self.cumulative_weights
+                    .partition_point(|weight| *weight <= value)
+            }
+            PickMode::Sequential | PickMode::Unique => {
+                if iteration as usize >= len {
+                    bail!("Data source exhausted after {len} item(s)");
+                }
+                if self.mode == PickMode::Unique {
+                    self.permutation[iteration as usize]
+                } else {
+                    iteration as usize
+                }
+            }
+        };
+        Ok(&self.items[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_circular_succeeds() {
+        let source = DataSource::new(vec![1, 2, 3], PickMode::Circular).unwrap();
+
+        let picked: Vec<i32> = (0..5).map(|i| *source.pick(i).unwrap()).collect();
+
+        assert_eq!(picked, vec![1, 2, 3, 1, 2]);
+    }
+
+    #[test]
+    fn pick_sequential_succeeds() {
+        let source = DataSource::new(vec![1, 2, 3], PickMode::Sequential).unwrap();
+
+        let picked: Vec<i32> = (0..3).map(|i| *source.pick(i).unwrap()).collect();
+
+        assert_eq!(picked, vec![1, 2, 3]);
+        assert_eq!(
+            source.pick(3).unwrap_err().to_string(),
+            "Data source exhausted after 3 item(s)"
+        );
+    }
+
+    #[test]
+    fn pick_unique_succeeds() {
+        let source = DataSource::new((0..100).collect(), PickMode::Unique).unwrap();
+
+        let mut picked: Vec<i32> = (0..100).map(|i| *source.pick(i).unwrap()).collect();
+        picked.sort();
+
+        assert_eq!(picked, (0..100).collect::<Vec<i32>>());
+        assert!(source.pick(100).is_err());
+    }
+
+    #[test]
+    fn pick_random_succeeds() {
+        let source = DataSource::new(vec![1, 2, 3], PickMode::Random).unwrap();
+
+        for i in 0..100 {
+            assert!((1..=3).contains(source.pick(i).unwrap()));
+        }
+    }
+
+    #[test]
+    fn pick_weighted_succeeds() {
+        let source =
+            DataSource::with_weights(vec![1, 2, 3], vec![0, 3, 1], PickMode::Weighted).unwrap();
+
+        let mut counts = [0; 3];
+        for i in 0..1000 {
+            counts[*source.pick(i).unwrap() as usize - 1] += 1;
+        }
+
+        assert_eq!(counts[0], 0);
+        assert!(counts[1] > counts[2]);
+        assert_eq!(counts[1] + counts[2], 1000);
+    }
+
+    #[test]
+    fn with_zero_weights_fails() {
+        let err = DataSource::with_weights(vec![1, 2], vec![0, 0], PickMode::Weighted).unwrap_err();
+
+        assert_eq!(err.to_string(), "Sum of weights must be greater than zero");
+    }
+
+    #[test]
+    fn new_empty_fails() {
+        let err = DataSource::<i32>::new(vec![], PickMode::Circular).unwrap_err();
+
+        assert_eq!(err.to_string(), "Data source cannot be empty");
+    }
+}
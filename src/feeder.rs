@@ -0,0 +1,175 @@
+use crate::data_source::{DataSource, PickMode};
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::fs;
+
+/// Variables of a single feeder row, keyed by column name.
+pub type Row = Arc<HashMap<String, String>>;
+
+/// A source of variables for parameterized requests.
+///
+/// Every request picks a row, and the values of the row are available as `{{column}}` in the URL,
+/// headers and bodies of the request.
+#[derive(Debug, Clone)]
+pub struct Feeder {
+    rows: DataSource<Row>,
+    columns: BTreeSet<String>,
+}
+
+impl Feeder {
+    /// Reads a feeder file.
+    ///
+    /// Files with a `.jsonl`, `.ndjson` or `.json` extension are read as JSON Lines, where every
+    /// line is a JSON object. Any other file is read as CSV with a header row.
+    ///
+    /// # Parameters
+    ///
+    /// * `path`: Feeder file.
+    /// * `mode`: How to pick the rows.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `Feeder` instance if successful.
+    pub async fn from_file(path: &Path, mode: PickMode) -> Result<Self> {
+        if !path.is_file() {
+            bail!(
+                "Feeder file '{}' does not exist or is not a file",
+                path.display()
+            );
+        }
+        let content = fs::read_to_string(path).await?;
+        let rows = match path.extension().and_then(|e| e.to_str()) {
+            Some("jsonl" | "ndjson" | "json") => Self::parse_jsonl(&content)?,
+            _ => Self::parse_csv(&content)?,
+        };
+        Self::new(rows, mode)
+    }
+
+    /// Creates a new `Feeder` from rows.
+    ///
+    /// # Parameters
+    ///
+    /// * `rows`: Rows of variables.
+    /// * `mode`: How to pick the rows.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `Feeder` instance if successful.
+    pub fn new(rows: Vec<HashMap<String, String>>, mode: PickMode) -> Result<Self> {
+        let columns = rows.iter().flat_map(|row| row.keys().cloned()).collect();
+        let rows = DataSource::new(rows.into_iter().map(Arc::new).collect(), mode)
+            .context("Feeder does not contain any rows")?;
+        Ok(Feeder { rows, columns })
+    }
+
+    /// Returns the names of all columns.
+    pub fn columns(&self) -> &BTreeSet<String> {
+        &self.columns
+    }
+
+    /// Picks the row of the specified iteration.
+    pub fn pick(&self, iteration: u64) -> Result<&Row> {
+        self.rows.pick(iteration)
+    }
+
+    fn parse_csv(content: &str) -> Result<Vec<HashMap<String, String>>> {
+        let mut reader = csv::Reader::from_reader(content.as_bytes());
+        let headers = reader.headers()?.clone();
+        reader
+            .records()
+            .map(|record| {
+                let record = record?;
+                Ok(headers
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                    .collect())
+            })
+            .collect()
+    }
+
+    fn parse_jsonl(content: &str) -> Result<Vec<HashMap<String, String>>> {
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let object: HashMap<String, Value> = serde_json::from_str(line)
+                    .context(format!("Invalid feeder row on line {}", i + 1))?;
+                Ok(object
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = match value {
+                            Value::String(s) => s,
+                            value => value.to_string(),
+                        };
+                        (name, value)
+                    })
+                    .collect())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_csv_succeeds() {
+        let rows = Feeder::parse_csv("user_id,token\n1,abc\n2,def\n").unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["user_id"], "1");
+        assert_eq!(rows[1]["token"], "def");
+    }
+
+    #[test]
+    fn parse_jsonl_succeeds() {
+        let rows =
+            Feeder::parse_jsonl("{\"user_id\": 1, \"token\": \"abc\"}\n\n{\"sku\": \"x\"}\n")
+                .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["user_id"], "1");
+        assert_eq!(rows[0]["token"], "abc");
+        assert_eq!(rows[1]["sku"], "x");
+    }
+
+    #[test]
+    fn parse_invalid_jsonl_fails() {
+        let err = Feeder::parse_jsonl("{\"user_id\": 1}\n[1]\n").unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid feeder row on line 2");
+    }
+
+    #[test]
+    fn new_succeeds() {
+        let feeder = Feeder::new(
+            vec![
+                HashMap::from([("a".to_owned(), "1".to_owned())]),
+                HashMap::from([("b".to_owned(), "2".to_owned())]),
+            ],
+            PickMode::Circular,
+        )
+        .unwrap();
+
+        assert_eq!(feeder.columns().iter().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(feeder.pick(2).unwrap()["a"], "1");
+    }
+
+    #[tokio::test]
+    async fn from_file_does_not_exist_fails() {
+        let err = Feeder::from_file(Path::new("doesnotexist.csv"), PickMode::Circular)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Feeder file 'doesnotexist.csv' does not exist or is not a file"
+        );
+    }
+}
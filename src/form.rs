@@ -0,0 +1,327 @@
+use crate::template::{RenderContext, Template};
+use anyhow::{Context, Result, bail};
+use bytes::{BufMut, Bytes, BytesMut};
+use reqwest::Url;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tokio::fs;
+use uuid::Uuid;
+
+/// Content type of a file part without a `type` parameter.
+const DEFAULT_FILE_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// A field of a `multipart/form-data` body in the syntax of curl's `-F` option.
+///
+/// * `name=value`: A text field.
+/// * `name=@path`: A file upload, optionally followed by `;type=...` and `;filename=...`.
+/// * `name=<path`: A text field whose value is the content of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormField {
+    /// Name of the field.
+    pub name: String,
+
+    /// Value of the field.
+    pub value: FormValue,
+}
+
+/// Value of a [`FormField`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormValue {
+    /// A text value, which is rendered if templating is enabled.
+    Text(String),
+
+    /// A text value that is read from a file.
+    TextFile(PathBuf),
+
+    /// A file upload.
+    File {
+        /// File to upload.
+        path: PathBuf,
+
+        /// Content type of the part. Defaults to `application/octet-stream`.
+        content_type: Option<String>,
+
+        /// File name of the part. Defaults to the name of the file.
+        filename: Option<String>,
+    },
+}
+
+impl FormField {
+    /// Parses a field from its name and its value in the syntax of curl's `-F` option.
+    pub fn new(name: &str, value: &str) -> Result<Self> {
+        if name.is_empty() {
+            bail!("Form field name cannot be empty");
+        }
+        let value = if let Some(file) = value.strip_prefix('@') {
+            let mut params = file.split(';');
+            let path = params.next().unwrap_or_default();
+            if path.is_empty() {
+                bail!("Missing file of form field '{name}'");
+            }
+            let mut content_type = None;
+            let mut filename = None;
+            for param in params {
+                match param.split_once('=') {
+                    // The content type is written as is into the header of the part.
+                    Some(("type", value)) if value.contains(['\r', '\n']) => {
+                        bail!("Invalid content type of form field '{name}'")
+                    }
+                    Some(("type", value)) => content_type = Some(value.to_owned()),
+                    Some(("filename", value)) => filename = Some(value.to_owned()),
+                    _ => bail!("Invalid parameter '{param}' of form field '{name}'"),
+                }
+            }
+            FormValue::File {
+                path: path.into(),
+                content_type,
+                filename,
+            }
+        } else if let Some(path) = value.strip_prefix('<') {
+            FormValue::TextFile(path.into())
+        } else {
+            FormValue::Text(value.to_owned())
+        };
+        Ok(FormField {
+            name: name.to_owned(),
+            value,
+        })
+    }
+}
+
+impl FromStr for FormField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('=') {
+            Some((name, value)) => FormField::new(name, value),
+            None => bail!("'{s}' is not a valid form field, expected NAME=VALUE"),
+        }
+    }
+}
+
+/// A `multipart/form-data` body whose files are read once and whose text values are rendered for
+/// every request.
+#[derive(Debug, Clone)]
+pub(crate) struct Multipart {
+    boundary: String,
+    parts: Vec<(Bytes, MultipartContent)>,
+
+    /// The body, if it is the same for every request, so it is only built once.
+    rendered: Option<Bytes>,
+}
+
+#[derive(Debug, Clone)]
+enum MultipartContent {
+    Text(Template),
+    File(Bytes),
+}
+
+impl Multipart {
+    /// Reads the files of the fields and parses their text values with `template`.
+    pub(crate) async fn new(
+        fields: &[FormField],
+        template: impl Fn(&str) -> Result<Template>,
+    ) -> Result<Self> {
+        let boundary = format!("load-rs-{}", Uuid::new_v4().simple());
+        let mut parts = Vec::new();
+        for field in fields {
+            let name = escape(&field.name);
+            let part = match &field.value {
+                FormValue::Text(value) => {
+                    let header = format!("Content-Disposition: form-data; name=\"{name}\"\r\n");
+                    (header, MultipartContent::Text(template(value)?))
+                }
+                FormValue::TextFile(path) => {
+                    let value = fs::read_to_string(path).await.context(format!(
+                        "Failed to read form field '{}' from '{}'",
+                        field.name,
+                        path.display()
+                    ))?;
+                    let header = format!("Content-Disposition: form-data; name=\"{name}\"\r\n");
+                    (header, MultipartContent::Text(template(&value)?))
+                }
+                FormValue::File {
+                    path,
+                    content_type,
+                    filename,
+                } => {
+                    let content = fs::read(path).await.context(format!(
+                        "Failed to read form field '{}' from '{}'",
+                        field.name,
+                        path.display()
+                    ))?;
+                    let filename = filename.clone().unwrap_or_else(|| {
+                        path.file_name()
+                            .map(|f| f.to_string_lossy().into_owned())
+                            .unwrap_or_default()
+                    });
+                    let header = format!(
+                        "Content-Disposition: form-data; name=\"{name}\"; filename=\"{}\"\r\nContent-Type: {}\r\n",
+                        escape(&filename),
+                        content_type.as_deref().unwrap_or(DEFAULT_FILE_CONTENT_TYPE)
+                    );
+                    (header, MultipartContent::File(content.into()))
+                }
+            };
+            parts.push((Bytes::from(part.0), part.1));
+        }
+        let mut multipart = Multipart {
+            boundary,
+            parts,
+            rendered: None,
+        };
+        if multipart.is_static() {
+            multipart.rendered = Some(multipart.render(&RenderContext::default()));
+        }
+        Ok(multipart)
+    }
+
+    /// Returns the `Content-Type` header of the body, including its boundary.
+    pub(crate) fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Returns `true` if the body is the same for every request.
+    pub(crate) fn is_static(&self) -> bool {
+        self.parts.iter().all(|(_, content)| match content {
+            MultipartContent::Text(template) => template.is_static(),
+            MultipartContent::File(_) => true,
+        })
+    }
+
+    /// Renders the body.
+    pub(crate) fn render(&self, context: &RenderContext) -> Bytes {
+        if let Some(rendered) = &self.rendered {
+            return rendered.clone();
+        }
+        let mut body = BytesMut::new();
+        for (header, content) in &self.parts {
+            body.put_slice(format!("--{}\r\n", self.boundary).as_bytes());
+            body.put_slice(header);
+            body.put_slice(b"\r\n");
+            match content {
+                MultipartContent::Text(template) => {
+                    body.put_slice(template.render(context).as_bytes())
+                }
+                MultipartContent::File(content) => body.put_slice(content),
+            }
+            body.put_slice(b"\r\n");
+        }
+        body.put_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        body.freeze()
+    }
+}
+
+/// Encodes the fields of an `application/x-www-form-urlencoded` body.
+pub(crate) fn url_encode<'a>(fields: impl IntoIterator<Item = (&'a str, String)>) -> String {
+    let mut form = Url::parse("http://localhost").expect("Valid URL");
+    {
+        let mut query_pairs = form.query_pairs_mut();
+        for (name, value) in fields {
+            query_pairs.append_pair(name, &value);
+        }
+    }
+    form.query().unwrap_or_default().to_owned()
+}
+
+/// Escapes the quotes and line breaks of a name in a `Content-Disposition` header, like browsers
+/// do.
+fn escape(s: &str) -> String {
+    s.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_succeeds() {
+        assert_eq!(
+            FormField::from_str("title=Hello=World").unwrap(),
+            FormField {
+                name: "title".to_owned(),
+                value: FormValue::Text("Hello=World".to_owned()),
+            }
+        );
+        assert_eq!(
+            FormField::from_str("notes=<notes.txt").unwrap().value,
+            FormValue::TextFile("notes.txt".into())
+        );
+        assert_eq!(
+            FormField::from_str("file=@photo.png;type=image/png;filename=me.png")
+                .unwrap()
+                .value,
+            FormValue::File {
+                path: "photo.png".into(),
+                content_type: Some("image/png".to_owned()),
+                filename: Some("me.png".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_invalid_field_fails() {
+        assert_eq!(
+            FormField::from_str("title").unwrap_err().to_string(),
+            "'title' is not a valid form field, expected NAME=VALUE"
+        );
+        assert_eq!(
+            FormField::from_str("=1").unwrap_err().to_string(),
+            "Form field name cannot be empty"
+        );
+        assert_eq!(
+            FormField::from_str("file=@a.png;size=1")
+                .unwrap_err()
+                .to_string(),
+            "Invalid parameter 'size=1' of form field 'file'"
+        );
+        assert_eq!(
+            FormField::from_str("file=@a.png;type=image/png\r\nX-Injected: 1")
+                .unwrap_err()
+                .to_string(),
+            "Invalid content type of form field 'file'"
+        );
+    }
+
+    #[tokio::test]
+    async fn render_multipart_succeeds() {
+        let fields = [
+            FormField::from_str("title=Hello {{seq}}").unwrap(),
+            FormField::from_str("file=@tests/test_requests/test1.json;type=application/json")
+                .unwrap(),
+        ];
+        let multipart = Multipart::new(&fields, Template::parse).await.unwrap();
+        assert!(multipart.rendered.is_none());
+
+        let body = multipart.render(&RenderContext {
+            seq: 7,
+            variables: None,
+        });
+        let boundary = &multipart.boundary;
+        let file = fs::read_to_string("tests/test_requests/test1.json")
+            .await
+            .unwrap();
+        assert_eq!(
+            multipart.content_type(),
+            format!("multipart/form-data; boundary={boundary}")
+        );
+        assert_eq!(
+            String::from_utf8(body.to_vec()).unwrap(),
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nHello 7\r\n\
+                 --{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"test1.json\"\r\nContent-Type: application/json\r\n\r\n{file}\r\n\
+                 --{boundary}--\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn url_encode_succeeds() {
+        assert_eq!(
+            url_encode([("q", "red shoes".to_owned()), ("a&b", "1=2".to_owned())]),
+            "q=red+shoes&a%26b=1%3D2"
+        );
+    }
+}
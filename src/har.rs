@@ -0,0 +1,316 @@
+use crate::{HttpMethod, RecordedRequest, RequestTemplate};
+use anyhow::{Context, Result, bail};
+use chrono::DateTime;
+use reqwest::Url;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use tokio::fs;
+
+/// Request headers that are set by the HTTP client rather than replayed from a HAR file.
+const SKIPPED_HEADERS: [&str; 4] = ["host", "content-length", "connection", "transfer-encoding"];
+
+/// JSON representation of a HAR file.
+#[derive(Debug, Deserialize)]
+struct HarFile {
+    log: HarLog,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarLog {
+    entries: Vec<HarEntryTemplate>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarEntryTemplate {
+    started_date_time: String,
+    request: HarRequest,
+    response: Option<HarResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<HarHeader>,
+    post_data: Option<HarPostData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarHeader {
+    name: String,
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarPostData {
+    mime_type: Option<String>,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarResponse {
+    content: Option<HarContent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarContent {
+    mime_type: Option<String>,
+}
+
+/// Specifies which entries of a HAR file to replay. An empty list matches every entry.
+#[derive(Debug, Clone, Default)]
+pub struct HarFilter {
+    /// Domains of the request URLs, including their subdomains, e.g. `example.com`.
+    pub domains: Vec<String>,
+
+    /// Prefixes of the response content types, e.g. `application/json` or `text/`.
+    pub content_types: Vec<String>,
+}
+
+impl HarFilter {
+    fn matches(&self, url: &Url, content_type: Option<&str>) -> bool {
+        let domain_matches = self.domains.is_empty()
+            || url.host_str().is_some_and(|host| {
+                self.domains.iter().any(|domain| {
+                    host.eq_ignore_ascii_case(domain)
+                        || host
+                            .to_ascii_lowercase()
+                            .ends_with(&format!(".{}", domain.to_ascii_lowercase()))
+                })
+            });
+        let content_type_matches = self.content_types.is_empty()
+            || content_type.is_some_and(|content_type| {
+                let content_type = content_type.to_ascii_lowercase();
+                self.content_types
+                    .iter()
+                    .any(|prefix| content_type.starts_with(&prefix.to_ascii_lowercase()))
+            });
+        domain_matches && content_type_matches
+    }
+}
+
+/// The requests of a browser session that is recorded as a HAR (HTTP Archive) file.
+#[derive(Debug, Clone)]
+pub struct Har {
+    entries: Vec<RecordedRequest>,
+}
+
+impl Har {
+    /// Reads a HAR file.
+    ///
+    /// Every entry becomes a request with the method, URL, headers and body of the recorded
+    /// request. Entries with an HTTP method that is not supported, e.g. `OPTIONS`, are skipped.
+    ///
+    /// # Parameters
+    ///
+    /// * `path`: HAR file.
+    /// * `filter`: Which entries to replay.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `Har` instance if successful.
+    pub async fn from_file(path: &Path, filter: &HarFilter) -> Result<Self> {
+        if !path.is_file() {
+            bail!(
+                "HAR file '{}' does not exist or is not a file",
+                path.display()
+            );
+        }
+        let content = fs::read_to_string(path).await?;
+        let har = Self::parse(&content, filter)
+            .context(format!("Invalid HAR file '{}'", path.display()))?;
+        if har.is_empty() {
+            bail!(
+                "HAR file '{}' does not contain any matching entries",
+                path.display()
+            );
+        }
+        Ok(har)
+    }
+
+    fn parse(content: &str, filter: &HarFilter) -> Result<Self> {
+        let har: HarFile = serde_json::from_str(content)?;
+        let mut entries = Vec::new();
+        for (i, entry) in har.log.entries.into_iter().enumerate() {
+            let started = DateTime::parse_from_rfc3339(&entry.started_date_time)
+                .context(format!("Invalid startedDateTime of entry {}", i + 1))?;
+            let url = Url::parse(&entry.request.url)
+                .context(format!("Invalid URL of entry {}", i + 1))?;
+            let content_type = entry
+                .response
+                .as_ref()
+                .and_then(|response| response.content.as_ref())
+                .and_then(|content| content.mime_type.as_deref());
+            if HttpMethod::from_str(&entry.request.method).is_err()
+                || !filter.matches(&url, content_type)
+            {
+                continue;
+            }
+            entries.push((started, Self::to_template(entry.request)));
+        }
+        entries.sort_by_key(|(started, _)| *started);
+        let first = entries.first().map(|(started, _)| *started);
+        let entries = entries
+            .into_iter()
+            .map(|(started, request)| RecordedRequest {
+                request,
+                offset: first
+                    .and_then(|first| (started - first).to_std().ok())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        Ok(Har { entries })
+    }
+
+    fn to_template(request: HarRequest) -> RequestTemplate {
+        let mut headers: BTreeMap<String, String> = request
+            .headers
+            .into_iter()
+            .filter(|header| {
+                !header.name.starts_with(':')
+                    && !SKIPPED_HEADERS
+                        .iter()
+                        .any(|skipped| header.name.eq_ignore_ascii_case(skipped))
+            })
+            .map(|header| (header.name, header.value))
+            .collect();
+        let (mime_type, body) = request.post_data.map_or((None, None), |post_data| {
+            (post_data.mime_type, post_data.text)
+        });
+        if let Some(mime_type) = mime_type
+            && body.is_some()
+            && !headers
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case("content-type"))
+        {
+            headers.insert("content-type".to_owned(), mime_type);
+        }
+        RequestTemplate {
+            name: None,
+            weight: None,
+            method: Some(request.method),
+            url: Some(request.url),
+            path: None,
+            query: BTreeMap::new(),
+            headers,
+            body,
+            binary_body: None,
+            form: BTreeMap::new(),
+            form_urlencoded: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the entries of the HAR file, ordered by their time.
+    pub fn entries(&self) -> &[RecordedRequest] {
+        &self.entries
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const HAR: &str = r#"{"log": {"entries": [
+        {
+            "startedDateTime": "2025-01-01T00:00:01.500Z",
+            "request": {
+                "method": "POST",
+                "url": "https://api.example.com/orders?x=1",
+                "headers": [
+                    {"name": ":authority", "value": "api.example.com"},
+                    {"name": "Content-Length", "value": "9"},
+                    {"name": "Authorization", "value": "Bearer abc"}
+                ],
+                "postData": {"mimeType": "application/json", "text": "{\"id\": 1}"}
+            },
+            "response": {"content": {"mimeType": "application/json; charset=utf-8"}}
+        },
+        {
+            "startedDateTime": "2025-01-01T00:00:00Z",
+            "request": {"method": "GET", "url": "https://www.example.com/", "headers": []},
+            "response": {"content": {"mimeType": "text/html"}}
+        },
+        {
+            "startedDateTime": "2025-01-01T00:00:02Z",
+            "request": {"method": "OPTIONS", "url": "https://api.example.com/orders"}
+        },
+        {
+            "startedDateTime": "2025-01-01T00:00:03Z",
+            "request": {"method": "GET", "url": "https://cdn.other.com/app.js"},
+            "response": {"content": {"mimeType": "application/javascript"}}
+        }
+    ]}}"#;
+
+    #[test]
+    fn parse_succeeds() {
+        let har = Har::parse(HAR, &HarFilter::default()).unwrap();
+
+        assert_eq!(har.len(), 3);
+        let entries = har.entries();
+        assert_eq!(entries[0].offset, Duration::ZERO);
+        assert_eq!(
+            entries[0].request.url.as_deref(),
+            Some("https://www.example.com/")
+        );
+        assert_eq!(entries[1].offset, Duration::from_millis(1500));
+        assert_eq!(entries[1].request.method.as_deref(), Some("POST"));
+        assert_eq!(entries[1].request.body.as_deref(), Some("{\"id\": 1}"));
+        assert_eq!(
+            entries[1].request.headers,
+            BTreeMap::from([
+                ("Authorization".to_owned(), "Bearer abc".to_owned()),
+                ("content-type".to_owned(), "application/json".to_owned()),
+            ])
+        );
+        assert_eq!(entries[2].offset, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn parse_with_filter_succeeds() {
+        let filter = HarFilter {
+            domains: vec!["example.com".to_owned()],
+            content_types: vec!["application/json".to_owned()],
+        };
+
+        let har = Har::parse(HAR, &filter).unwrap();
+
+        assert_eq!(har.len(), 1);
+        assert_eq!(
+            har.entries()[0].request.url.as_deref(),
+            Some("https://api.example.com/orders?x=1")
+        );
+        assert_eq!(har.entries()[0].offset, Duration::ZERO);
+    }
+
+    #[test]
+    fn parse_invalid_har_fails() {
+        assert!(Har::parse("{}", &HarFilter::default()).is_err());
+        assert_eq!(
+            Har::parse(
+                r#"{"log": {"entries": [{"startedDateTime": "x", "request": {"method": "GET", "url": "/"}}]}}"#,
+                &HarFilter::default()
+            )
+            .unwrap_err()
+            .to_string(),
+            "Invalid startedDateTime of entry 1"
+        );
+    }
+}
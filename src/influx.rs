@@ -0,0 +1,354 @@
+use crate::LoadTestRunner;
+use crate::metrics::{Recorder, Sample};
+use anyhow::{Context, Result, bail};
+use futures::future::BoxFuture;
+use reqwest::Client;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+
+/// Name of the measurement written by the [`InfluxExporter`].
+const MEASUREMENT: &str = "load_rs";
+
+/// Destination of the InfluxDB line protocol output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InfluxDestination {
+    /// Lines are appended to a file.
+    File(PathBuf),
+
+    /// Lines are sent to an HTTP write endpoint, e.g. `http://localhost:8086/write?db=load`.
+    Http(String),
+}
+
+impl InfluxDestination {
+    /// Parses a destination, which is an HTTP write endpoint if it starts with `http://` or
+    /// `https://`, or a file otherwise.
+    pub fn parse(destination: &str) -> Self {
+        if destination.starts_with("http://") || destination.starts_with("https://") {
+            InfluxDestination::Http(destination.to_owned())
+        } else {
+            InfluxDestination::File(destination.into())
+        }
+    }
+}
+
+/// Aggregated requests of a single `(name, endpoint, status)` tag set within an interval.
+#[derive(Debug, Default)]
+struct Bucket {
+    success: u64,
+    failures: u64,
+    total_duration: Duration,
+    durations: Vec<Duration>,
+}
+
+/// Aggregated requests within an interval, keyed by `(name, endpoint, status)`.
+type Interval = BTreeMap<(String, String, String), Bucket>;
+
+/// A [`Recorder`] that aggregates requests into fixed intervals and writes each interval as
+/// InfluxDB line protocol.
+///
+/// Every line is tagged with the run ID, the name, the endpoint, the status and any user-supplied tags.
+/// Completed intervals are written periodically while the run is in progress, and the remaining
+/// intervals are written once the run has completed. Lines that fail to be written are dropped and
+/// counted by [`Recorder::dropped`].
+#[derive(Debug)]
+pub struct InfluxExporter {
+    destination: InfluxDestination,
+    interval: Duration,
+    tags: String,
+    buckets: Mutex<BTreeMap<u128, Interval>>,
+    dropped: AtomicU64,
+    client: Client,
+}
+
+impl InfluxExporter {
+    /// Creates a new `InfluxExporter` and starts writing completed intervals periodically in the
+    /// background.
+    ///
+    /// # Parameters
+    ///
+    /// * `destination`: Where to write the line protocol to.
+    /// * `interval`: Length of each interval.
+    /// * `run_id`: Run ID to tag every line with.
+    /// * `tags`: Tags in "key=value" format to add to every line.
+    /// * `client`: HTTP client to write the line protocol to an HTTP endpoint with.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `InfluxExporter` instance if successful.
+    pub async fn new(
+        destination: InfluxDestination,
+        interval: Duration,
+        run_id: &str,
+        tags: &[String],
+        client: Client,
+    ) -> Result<Arc<Self>> {
+        if interval.is_zero() {
+            bail!("InfluxDB interval cannot be zero");
+        }
+        if let InfluxDestination::File(path) = &destination {
+            fs::write(path, "").await?;
+        }
+        let mut formatted_tags = format!(",run_id={}", Self::escape(run_id));
+        for tag in tags {
+            let (key, value) = tag
+                .split_once('=')
+                .context(format!("Invalid tag format: {tag}"))?;
+            let _ = write!(
+                formatted_tags,
+                ",{}={}",
+                Self::escape(key.trim()),
+                Self::escape(value.trim())
+            );
+        }
+        let exporter = Arc::new(InfluxExporter {
+            destination,
+            interval,
+            tags: formatted_tags,
+            buckets: Mutex::new(BTreeMap::new()),
+            dropped: AtomicU64::new(0),
+            client,
+        });
+        let weak = Arc::downgrade(&exporter);
+        tokio::spawn(Self::write_periodically(weak, interval));
+        Ok(exporter)
+    }
+
+    async fn write_periodically(exporter: Weak<Self>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let Some(exporter) = exporter.upgrade() else {
+                break;
+            };
+            let current = exporter.bucket_of(SystemTime::now());
+            let lines = exporter.drain(|bucket| bucket < current);
+            exporter.write_or_drop(&lines).await;
+        }
+    }
+
+    /// Writes the lines and counts them as dropped if the write fails.
+    async fn write_or_drop(&self, lines: &str) {
+        if self.write(lines).await.is_err() {
+            self.dropped
+                .fetch_add(lines.lines().count() as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn bucket_of(&self, timestamp: SystemTime) -> u128 {
+        timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+            / self.interval.as_nanos()
+    }
+
+    /// Removes the intervals matching the predicate and formats them as line protocol.
+    fn drain<P>(&self, predicate: P) -> String
+    where
+        P: Fn(u128) -> bool,
+    {
+        let mut buckets = self.buckets.lock().unwrap();
+        let keys: Vec<u128> = buckets.keys().copied().filter(|b| predicate(*b)).collect();
+        let mut lines = String::new();
+        for key in keys {
+            let timestamp = key * self.interval.as_nanos();
+            for ((name, endpoint, status), mut bucket) in buckets.remove(&key).unwrap_or_default() {
+                let count = bucket.success + bucket.failures;
+                let mean = bucket.total_duration.as_secs_f64() * 1000.0 / count as f64;
+                let min = bucket.durations.iter().min().copied().unwrap_or_default();
+                let max = bucket.durations.iter().max().copied().unwrap_or_default();
+                let quantiles =
+                    LoadTestRunner::get_quantiles(&mut bucket.durations, &[0.5, 0.9, 0.95]);
+                let _ = writeln!(
+                    lines,
+                    "{MEASUREMENT}{},name={},endpoint={},status={} count={count}i,success={}i,failures={}i,mean={mean},min={},max={},p50={},p90={},p95={} {timestamp}",
+                    self.tags,
+                    Self::escape(&name),
+                    Self::escape(&endpoint),
+                    Self::escape(&status),
+                    bucket.success,
+                    bucket.failures,
+                    Self::millis(min),
+                    Self::millis(max),
+                    Self::millis(quantiles[0]),
+                    Self::millis(quantiles[1]),
+                    Self::millis(quantiles[2]),
+                );
+            }
+        }
+        lines
+    }
+
+    async fn write(&self, lines: &str) -> Result<()> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+        match &self.destination {
+            InfluxDestination::File(path) => {
+                let mut file = OpenOptions::new().append(true).open(path).await?;
+                file.write_all(lines.as_bytes()).await?;
+            }
+            InfluxDestination::Http(url) => {
+                self.client
+                    .post(url)
+                    .body(lines.to_owned())
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn millis(duration: Duration) -> f64 {
+        duration.as_secs_f64() * 1000.0
+    }
+
+    /// Escapes commas, equal signs and spaces in tag keys and values.
+    fn escape(value: &str) -> String {
+        value
+            .replace('\\', "\\\\")
+            .replace(',', "\\,")
+            .replace('=', "\\=")
+            .replace(' ', "\\ ")
+    }
+}
+
+impl Recorder for InfluxExporter {
+    fn record(&self, sample: &Sample) {
+        let key = self.bucket_of(sample.timestamp);
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets
+            .entry(key)
+            .or_default()
+            .entry((
+                sample.name.clone(),
+                sample.endpoint.clone(),
+                sample.status_label(),
+            ))
+            .or_default();
+        if sample.success {
+            bucket.success += 1;
+        } else {
+            bucket.failures += 1;
+        }
+        bucket.total_duration += sample.duration;
+        bucket.durations.push(sample.duration);
+    }
+
+    fn finish(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let lines = self.drain(|_| true);
+            self.write_or_drop(&lines).await;
+            Ok(())
+        })
+    }
+
+    fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(status: Option<u16>, millis: u64, secs: u64) -> Sample {
+        Sample {
+            name: "GET /get".to_owned(),
+            endpoint: "/get".to_owned(),
+            status,
+            success: status == Some(200),
+            duration: Duration::from_millis(millis),
+            timestamp: UNIX_EPOCH + Duration::from_secs(secs),
+            trace: None,
+        }
+    }
+
+    #[test]
+    fn parse_destination_succeeds() {
+        assert_eq!(
+            InfluxDestination::parse("http://localhost:8086/write?db=load"),
+            InfluxDestination::Http("http://localhost:8086/write?db=load".to_owned())
+        );
+        assert_eq!(
+            InfluxDestination::parse("/tmp/metrics.lp"),
+            InfluxDestination::File("/tmp/metrics.lp".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn finish_writes_intervals() {
+        let path = std::env::temp_dir().join("load-rs-influx-finish.lp");
+        let exporter = InfluxExporter::new(
+            InfluxDestination::File(path.clone()),
+            Duration::from_secs(10),
+            "run 1",
+            &["env=test".to_owned()],
+            Client::new(),
+        )
+        .await
+        .unwrap();
+        exporter.record(&sample(Some(200), 10, 100));
+        exporter.record(&sample(Some(200), 30, 105));
+        exporter.record(&sample(None, 5, 105));
+        exporter.record(&sample(Some(200), 20, 110));
+        exporter.finish().await.unwrap();
+
+        let output = fs::read_to_string(&path).await.unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "load_rs,run_id=run\\ 1,env=test,name=GET\\ /get,endpoint=/get,status=200 count=2i,success=2i,failures=0i,mean=20,min=10,max=30,p50=30,p90=30,p95=30 100000000000",
+                "load_rs,run_id=run\\ 1,env=test,name=GET\\ /get,endpoint=/get,status=error count=1i,success=0i,failures=1i,mean=5,min=5,max=5,p50=5,p90=5,p95=5 100000000000",
+                "load_rs,run_id=run\\ 1,env=test,name=GET\\ /get,endpoint=/get,status=200 count=1i,success=1i,failures=0i,mean=20,min=20,max=20,p50=20,p90=20,p95=20 110000000000",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn finish_counts_dropped_lines() {
+        let path = std::env::temp_dir().join("load-rs-influx-dropped.lp");
+        let exporter = InfluxExporter::new(
+            InfluxDestination::File(path.clone()),
+            Duration::from_secs(10),
+            "run",
+            &[],
+            Client::new(),
+        )
+        .await
+        .unwrap();
+        exporter.record(&sample(Some(200), 10, 100));
+        exporter.record(&sample(None, 5, 110));
+        // The file is appended to, so the write fails once it is removed.
+        fs::remove_file(&path).await.unwrap();
+        exporter.finish().await.unwrap();
+
+        assert_eq!(exporter.dropped(), 2);
+    }
+
+    #[tokio::test]
+    async fn new_invalid_tag_fails() {
+        let err = InfluxExporter::new(
+            InfluxDestination::Http("http://localhost:8086/write".to_owned()),
+            Duration::from_secs(1),
+            "run",
+            &["env".to_owned()],
+            Client::new(),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid tag format: env");
+    }
+}
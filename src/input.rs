@@ -0,0 +1,39 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::pin::Pin;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader, Lines};
+
+/// Path of a manifest or data file that is read from stdin instead, e.g. `-m -`.
+pub const STDIN: &str = "-";
+
+/// Lines of a file or of stdin.
+pub(crate) type InputLines = Lines<BufReader<Pin<Box<dyn AsyncRead + Send>>>>;
+
+/// Returns whether a path refers to stdin.
+pub fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == STDIN
+}
+
+/// Opens a file, or stdin if the path is [`STDIN`].
+async fn open(path: &Path) -> Result<Pin<Box<dyn AsyncRead + Send>>> {
+    if is_stdin(path) {
+        return Ok(Box::pin(tokio::io::stdin()));
+    }
+    let file = File::open(path)
+        .await
+        .context(format!("Failed to open '{}'", path.display()))?;
+    Ok(Box::pin(file))
+}
+
+/// Reads the lines of a file, or of stdin if the path is [`STDIN`], lazily.
+pub(crate) async fn lines(path: impl AsRef<Path>) -> Result<InputLines> {
+    Ok(BufReader::new(open(path.as_ref()).await?).lines())
+}
+
+/// Reads a whole file, or stdin if the path is [`STDIN`].
+pub(crate) async fn read(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    open(path.as_ref()).await?.read_to_end(&mut data).await?;
+    Ok(data)
+}
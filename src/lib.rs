@@ -1,21 +1,62 @@
-use anyhow::{Result, bail};
+pub mod access_log;
+pub mod compression;
+pub mod curl;
+pub mod data_source;
+pub mod feeder;
+pub mod form;
+pub mod har;
+pub mod influx;
+pub mod input;
+pub mod manifest;
+pub mod metrics;
+pub mod openapi;
+pub mod postman;
+pub mod prometheus;
+pub mod scenario;
+pub mod statsd;
+pub mod template;
+pub mod think_time;
+pub mod trace;
+pub mod upload;
+
+use crate::access_log::AccessLog;
+use crate::compression::BodyCompression;
+use crate::curl::CurlCommand;
+use crate::data_source::{DataSource, PickMode};
+use crate::feeder::Feeder;
+use crate::feeder::Row;
+use crate::form::{FormField, Multipart};
+use crate::har::Har;
+use crate::manifest::{DEFAULT_BLOCK_SIZE, ManifestReader};
+use crate::metrics::{Recorder, Sample};
+use crate::openapi::OpenApi;
+use crate::scenario::{Extractor, Scenario, Step};
+use crate::template::{RenderContext, Template};
+use crate::think_time::ThinkTime;
+use crate::trace::TraceContext;
+use crate::upload::BodyStreaming;
+use anyhow::{Context, Result, bail};
 use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
 use bytes::Bytes;
+use futures::stream::BoxStream;
 use futures::{Stream, StreamExt, stream};
 use rand::Rng;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use reqwest::{Certificate, Client, Identity, Response};
-use serde::Deserialize;
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{CONTENT_ENCODING, COOKIE, HeaderMap, HeaderName, HeaderValue, SET_COOKIE};
+use reqwest::{Certificate, Client, ClientBuilder, Identity, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::ffi::OsString;
+use std::fmt;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::time::{Duration, Instant};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::fs;
-use tokio::fs::File;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use uuid::Uuid;
 
 /// A load test runner responsible for configuring and executing a load test.
 #[derive(Debug, Clone)]
@@ -34,6 +75,76 @@ pub struct LoadTestRunner {
 
     /// HTTP client.
     client: Client,
+
+    /// TLS configuration of the HTTP client, used to create the clients of virtual users.
+    tls: TlsConfig,
+
+    /// Recorders that are fed every completed request.
+    recorders: Vec<Arc<dyn Recorder>>,
+
+    /// Ratio of requests to sample if W3C trace context injection is enabled.
+    trace_sample_ratio: Option<f64>,
+
+    /// Header to inject a unique request ID into, how to generate its value and the run ID.
+    request_id: Option<(HeaderName, RequestIdFormat, String)>,
+
+    /// Whether `{{...}}` expressions in the URL, headers and bodies are rendered.
+    templating: bool,
+
+    /// Source of variables for parameterized requests.
+    feeder: Option<Feeder>,
+
+    /// Whether to run virtual users, and whether they share the connection pool.
+    virtual_users: Option<VirtualUsers>,
+
+    /// Pause after every request, which does not count towards the latency statistics.
+    think_time: Option<ThinkTime>,
+
+    /// Minimum interval between the starts of two iterations of the same user.
+    pacing: Option<Duration>,
+
+    /// Whether manifest files are read lazily instead of all at once.
+    manifest_streaming: Option<ManifestStreaming>,
+
+    /// Compression of the request bodies.
+    body_compression: Option<BodyCompression>,
+
+    /// Whether data files are streamed from disk instead of being read into memory.
+    body_streaming: Option<BodyStreaming>,
+}
+
+/// TLS configuration of an HTTP client.
+#[derive(Debug, Clone)]
+struct TlsConfig {
+    insecure: bool,
+    ca_cert: Option<Certificate>,
+    identity: Option<Identity>,
+}
+
+/// Configuration of virtual users.
+#[derive(Debug, Clone, Copy)]
+struct VirtualUsers {
+    shared_connections: bool,
+}
+
+/// Configuration of reading manifest files lazily.
+#[derive(Debug, Clone, Copy)]
+struct ManifestStreaming {
+    looping: bool,
+    block_size: usize,
+}
+
+/// A virtual user that runs its iterations one after another with its own cookie jar and
+/// variables.
+struct VirtualUser {
+    /// HTTP client, which has its own connection pool and cookie jar unless connections are shared.
+    client: Client,
+
+    /// Cookie jar that is managed by the runner if the connection pool is shared.
+    cookies: Option<Arc<Jar>>,
+
+    /// Variables extracted by the scenario steps of the previous iterations.
+    variables: HashMap<String, String>,
 }
 
 /// Defines the allowed HTTP methods that the user can specify.
@@ -47,6 +158,36 @@ pub enum HttpMethod {
     Head,
 }
 
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let method = match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Head => "HEAD",
+        };
+        f.write_str(method)
+    }
+}
+
+impl FromStr for HttpMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "get" => Ok(HttpMethod::Get),
+            "post" => Ok(HttpMethod::Post),
+            "put" => Ok(HttpMethod::Put),
+            "delete" => Ok(HttpMethod::Delete),
+            "patch" => Ok(HttpMethod::Patch),
+            "head" => Ok(HttpMethod::Head),
+            _ => bail!("'{s}' is not a valid HTTP method"),
+        }
+    }
+}
+
 /// Represents the aggregated results of a load test run.
 #[derive(Debug, Clone)]
 pub struct LoadTestResult {
@@ -86,6 +227,19 @@ pub struct LoadTestResult {
 
     /// Requests per second.
     pub rps: f64,
+
+    /// Total number of request body bytes sent, after compression.
+    pub bytes_sent: u64,
+
+    /// Total number of request body bytes sent, before compression.
+    pub uncompressed_bytes_sent: u64,
+
+    /// Total number of samples that the recorders could not export.
+    pub dropped_samples: u64,
+
+    /// Results of every named group of requests, keyed by the name of the manifest entry, the
+    /// file name of the request body, or the HTTP method and the path of the URL.
+    pub groups: BTreeMap<String, LoadTestResult>,
 }
 
 impl LoadTestResult {
@@ -103,6 +257,187 @@ impl LoadTestResult {
             p90: Duration::default(),
             p95: Duration::default(),
             rps: 0.0,
+            bytes_sent: 0,
+            uncompressed_bytes_sent: 0,
+            dropped_samples: 0,
+            groups: BTreeMap::new(),
+        }
+    }
+}
+
+/// Specifies how to generate the value of the request ID header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestIdFormat {
+    /// A random UUID.
+    Uuid,
+
+    /// The run ID followed by the iteration number, e.g. `<run ID>-42`.
+    Iteration,
+}
+
+/// Per-request metadata that is injected into the request and recorded in the output files.
+struct RequestContext {
+    iteration: u64,
+    name: Option<String>,
+    endpoint: Option<String>,
+    trace: Option<TraceContext>,
+    request_id: Option<String>,
+
+    /// Size of the request body after and before compression.
+    body_size: (u64, u64),
+}
+
+impl RequestContext {
+    /// Creates a context without any metadata to inject.
+    fn empty(iteration: u64) -> Self {
+        RequestContext {
+            iteration,
+            name: None,
+            endpoint: None,
+            trace: None,
+            request_id: None,
+            body_size: (0, 0),
+        }
+    }
+}
+
+/// Body of a [`PreparedRequest`].
+#[derive(Debug, Clone)]
+enum BodyTemplate {
+    /// A body that is sent as is.
+    Static(Bytes),
+
+    /// A body that is rendered for every request.
+    Template(Template),
+
+    /// A base64-encoded body that is decoded for every request.
+    Base64(String),
+
+    /// An `application/x-www-form-urlencoded` body whose values are rendered for every request.
+    UrlEncoded(Vec<(String, Template)>),
+
+    /// A `multipart/form-data` body whose text values are rendered for every request.
+    Multipart(Multipart),
+
+    /// A file that is streamed from disk when the request is sent.
+    File(PathBuf),
+}
+
+impl BodyTemplate {
+    /// Returns the body if it is the same for every request.
+    fn render_static(&self) -> Option<Bytes> {
+        match self {
+            BodyTemplate::Static(body) => Some(body.clone()),
+            BodyTemplate::Base64(base64_body) => {
+                BASE64_STANDARD.decode(base64_body).ok().map(Bytes::from)
+            }
+            BodyTemplate::Multipart(multipart) if multipart.is_static() => {
+                Some(multipart.render(&RenderContext::default()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the `Content-Type` header of a form body.
+    fn content_type(&self) -> Option<String> {
+        match self {
+            BodyTemplate::UrlEncoded(_) => Some("application/x-www-form-urlencoded".to_owned()),
+            BodyTemplate::Multipart(multipart) => Some(multipart.content_type()),
+            _ => None,
+        }
+    }
+}
+
+/// Value of a header of a [`PreparedRequest`].
+#[derive(Debug, Clone)]
+enum HeaderTemplate {
+    /// A value that is sent as is, which need not be valid UTF-8.
+    Raw(HeaderValue),
+
+    /// A value that is rendered for every request.
+    Template(Template),
+}
+
+/// A request whose URL, headers and body are parsed once and rendered for every request.
+#[derive(Debug, Clone)]
+struct PreparedRequest {
+    /// Name of the group to report the results of the request under.
+    name: Option<String>,
+
+    /// HTTP method that overrides the method of the load test.
+    method: Option<HttpMethod>,
+    url: Template,
+    query: Vec<(String, Template)>,
+    headers: Vec<(String, HeaderTemplate)>,
+    body: BodyTemplate,
+
+    /// Body that is compressed once, because it is the same for every request.
+    compressed_body: Option<Bytes>,
+
+    /// Path of the URL before rendering, used to label the metrics of the request.
+    endpoint: String,
+}
+
+impl PreparedRequest {
+    /// Renders the URL, the headers and the body of the request.
+    fn render(&self, context: &RenderContext) -> Result<(String, HeaderMap, Bytes)> {
+        let mut url = self.url.render(context);
+        if !self.query.is_empty() {
+            let mut parsed_url = Url::parse(&url).context(format!("Invalid URL '{url}'"))?;
+            {
+                let mut query_pairs = parsed_url.query_pairs_mut();
+                for (name, value) in &self.query {
+                    query_pairs.append_pair(name, &value.render(context));
+                }
+            }
+            url = parsed_url.into();
+        }
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let value = match value {
+                HeaderTemplate::Raw(value) => value.clone(),
+                HeaderTemplate::Template(template) => {
+                    HeaderValue::from_str(&template.render(context))?
+                }
+            };
+            headers.append(HeaderName::from_str(name)?, value);
+        }
+        let body = match &self.body {
+            BodyTemplate::Static(body) => body.clone(),
+            BodyTemplate::Template(template) => Bytes::from(template.render(context)),
+            BodyTemplate::Base64(base64_body) => Bytes::from(BASE64_STANDARD.decode(base64_body)?),
+            BodyTemplate::UrlEncoded(fields) => Bytes::from(form::url_encode(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.render(context))),
+            )),
+            BodyTemplate::Multipart(multipart) => multipart.render(context),
+            BodyTemplate::File(_) => Bytes::new(),
+        };
+        Ok((url, headers, body))
+    }
+}
+
+/// Outcome of a single request that is fed to `process_stream`.
+struct RequestOutcome {
+    response: Result<Response>,
+    duration: Duration,
+    base_file_name: Option<OsString>,
+    context: RequestContext,
+}
+
+impl RequestOutcome {
+    /// Creates the outcome of a request that failed before it could be sent.
+    fn error(error: anyhow::Error, iteration: u64, base_file_name: Option<OsString>) -> Self {
+        let mut context = RequestContext::empty(iteration);
+        context.name = base_file_name
+            .as_ref()
+            .map(|f| f.to_string_lossy().into_owned());
+        RequestOutcome {
+            response: Err(error),
+            duration: Duration::default(),
+            base_file_name,
+            context,
         }
     }
 }
@@ -115,8 +450,15 @@ pub enum Body {
     /// The request body is provided directly as an in-memory byte slice.
     Data(Bytes),
 
-    /// The request body will be read from a single specified file.
+    /// The request body will be read from a single specified file, or from stdin if the path is
+    /// [`input::STDIN`].
     DataFile(PathBuf),
+
+    /// The request body is a `multipart/form-data` body of fields.
+    Multipart(Vec<FormField>),
+
+    /// The request body is an `application/x-www-form-urlencoded` body of names and values.
+    UrlEncoded(Vec<(String, String)>),
 }
 
 /// Specifies the order in which to process request body files from a directory.
@@ -127,6 +469,29 @@ pub enum Order {
 
     /// Process files in a random order.
     Random,
+
+    /// Process manifest entries in a random order in proportion to their `weight`.
+    Weighted,
+}
+
+/// Specifies how to replay recorded requests, e.g. from a HAR file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Replay {
+    /// Replay the requests at load in the specified order.
+    Order(Order),
+
+    /// Replay the requests at their original relative times, divided by a speed-up factor. After
+    /// the last request, the recording starts over after the average gap between its requests.
+    Timing(f64),
+}
+
+/// A recorded request, e.g. an entry of a HAR file or a line of an access log.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub(crate) request: RequestTemplate,
+
+    /// Time of the request relative to the first request of the recording.
+    pub offset: Duration,
 }
 
 /// Specifies which requests to include in the statistics.
@@ -142,13 +507,33 @@ pub enum Stats {
     All,
 }
 
-/// JSON representation of manifest request file.
-#[derive(Debug, Clone, Deserialize)]
-struct RequestTemplate {
-    #[serde(default)]
-    headers: HashMap<String, String>,
+/// JSON representation of manifest request file. Unknown fields are rejected, so that a misspelled
+/// field fails the same way at runtime as with `validate`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RequestTemplate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    weight: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    query: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     binary_body: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    form: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    form_urlencoded: BTreeMap<String, String>,
 }
 
 impl LoadTestRunner {
@@ -191,9 +576,11 @@ impl LoadTestRunner {
                 "Number of concurrency: {concurrency} must be less than number of requests: {requests}"
             );
         }
-        let mut builder = Client::builder()
-            .use_rustls_tls()
-            .danger_accept_invalid_certs(insecure.unwrap_or(false));
+        let mut tls = TlsConfig {
+            insecure: insecure.unwrap_or(false),
+            ca_cert: None,
+            identity: None,
+        };
         if let Some(ca_cert_path) = ca_cert {
             if !ca_cert_path.is_file() {
                 bail!(
@@ -202,21 +589,233 @@ impl LoadTestRunner {
                 );
             }
             let bytes = fs::read(ca_cert_path).await?;
-            let ca_cert_bytes = Certificate::from_pem(&bytes)?;
-            builder = builder.add_root_certificate(ca_cert_bytes);
+            tls.ca_cert = Some(Certificate::from_pem(&bytes)?);
         }
         if let (Some(cert_path), Some(key_path)) = (cert, key) {
-            builder = builder.identity(Self::create_identity(cert_path, key_path).await?);
+            tls.identity = Some(Self::create_identity(cert_path, key_path).await?);
         }
         Ok(LoadTestRunner {
             url: url.to_owned(),
             requests,
             concurrency,
             stats,
-            client: builder.build()?,
+            client: Self::client_builder(&tls).build()?,
+            tls,
+            recorders: Vec::new(),
+            trace_sample_ratio: None,
+            request_id: None,
+            templating: false,
+            feeder: None,
+            virtual_users: None,
+            think_time: None,
+            pacing: None,
+            manifest_streaming: None,
+            body_compression: None,
+            body_streaming: None,
         })
     }
 
+    /// Creates a new HTTP client with the TLS settings of the runner, e.g. for exporters that send
+    /// their data to a server.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `Client` instance if successful.
+    pub fn new_client(&self) -> Result<Client> {
+        Ok(Self::client_builder(&self.tls).build()?)
+    }
+
+    fn client_builder(tls: &TlsConfig) -> ClientBuilder {
+        let mut builder = Client::builder()
+            .use_rustls_tls()
+            .danger_accept_invalid_certs(tls.insecure);
+        if let Some(ca_cert) = &tls.ca_cert {
+            builder = builder.add_root_certificate(ca_cert.clone());
+        }
+        if let Some(identity) = &tls.identity {
+            builder = builder.identity(identity.clone());
+        }
+        builder
+    }
+
+    /// Adds a recorder that is fed every completed request, e.g. to export live metrics.
+    ///
+    /// # Parameters
+    ///
+    /// * `recorder`: A recorder.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LoadTestRunner` with the recorder added.
+    pub fn with_recorder(mut self, recorder: Arc<dyn Recorder>) -> Self {
+        self.recorders.push(recorder);
+        self
+    }
+
+    /// Enables injecting a unique W3C `traceparent` header into every request.
+    ///
+    /// # Parameters
+    ///
+    /// * `sample_ratio`: Ratio of requests between 0.0 and 1.0 to mark as sampled.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the `LoadTestRunner` with trace context injection enabled,
+    /// or an error if the sample ratio is not between 0.0 and 1.0.
+    pub fn with_trace_context(mut self, sample_ratio: f64) -> Result<Self> {
+        self.trace_sample_ratio = Some(trace::check_sample_ratio(sample_ratio)?);
+        Ok(self)
+    }
+
+    /// Enables injecting a unique request ID header into every request.
+    ///
+    /// # Parameters
+    ///
+    /// * `header`: Name of the request ID header, e.g. `X-Request-Id`.
+    /// * `format`: How to generate the value of the request ID header.
+    /// * `run_id`: ID of the run, which prefixes the request IDs in the `Iteration` format.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LoadTestRunner` with request ID injection enabled.
+    pub fn with_request_id(
+        mut self,
+        header: HeaderName,
+        format: RequestIdFormat,
+        run_id: &str,
+    ) -> Self {
+        self.request_id = Some((header, format, run_id.to_owned()));
+        self
+    }
+
+    /// Enables rendering `{{...}}` expressions in the URL, the headers and the bodies from data,
+    /// data files and manifests for every request. See [`Template`] for the supported expressions.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LoadTestRunner` with templating enabled.
+    pub fn with_templating(mut self) -> Self {
+        self.templating = true;
+        self
+    }
+
+    /// Adds a feeder whose rows supply `{{column}}` variables to every request. This also enables
+    /// templating.
+    ///
+    /// # Parameters
+    ///
+    /// * `feeder`: A feeder.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LoadTestRunner` with the feeder added.
+    pub fn with_feeder(mut self, feeder: Feeder) -> Self {
+        self.templating = true;
+        self.feeder = Some(feeder);
+        self
+    }
+
+    /// Enables virtual users. Instead of sending independent requests, `concurrency` virtual users
+    /// run the iterations one after another, each with its own cookie jar and the variables
+    /// extracted by its previous scenario iterations.
+    ///
+    /// # Parameters
+    ///
+    /// * `shared_connections`: Whether the virtual users share the connection pool. Otherwise,
+    ///   every virtual user has its own connection pool.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LoadTestRunner` with virtual users enabled.
+    pub fn with_virtual_users(mut self, shared_connections: bool) -> Self {
+        self.virtual_users = Some(VirtualUsers { shared_connections });
+        self
+    }
+
+    /// Adds a think time, which is a pause after every request of a user. A scenario step may
+    /// override it with its own think time. The pause does not count towards the latency
+    /// statistics.
+    ///
+    /// # Parameters
+    ///
+    /// * `think_time`: A think time.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LoadTestRunner` with the think time added.
+    pub fn with_think_time(mut self, think_time: ThinkTime) -> Self {
+        self.think_time = Some(think_time);
+        self
+    }
+
+    /// Enables iteration pacing, where every user starts an iteration at most once per interval.
+    /// If an iteration takes longer than the interval, the next iteration starts right away.
+    ///
+    /// # Parameters
+    ///
+    /// * `interval`: Interval between the starts of two iterations of the same user.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LoadTestRunner` with iteration pacing enabled.
+    pub fn with_pacing(mut self, interval: Duration) -> Self {
+        self.pacing = Some(interval);
+        self
+    }
+
+    /// Enables reading manifest files lazily, so that only a block of lines is in memory at a time
+    /// instead of the whole file. The lines of a block are sent in order for the sequential order,
+    /// shuffled for the random order, and sampled by their weights for the weighted order. Unless
+    /// the file loops, every request fails once the whole file has been read.
+    ///
+    /// # Parameters
+    ///
+    /// * `looping`: Whether to start over at the end of the file.
+    /// * `block_size`: Maximum number of lines in memory.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LoadTestRunner` with manifest streaming enabled.
+    pub fn with_manifest_streaming(mut self, looping: bool, block_size: usize) -> Self {
+        self.manifest_streaming = Some(ManifestStreaming {
+            looping,
+            block_size,
+        });
+        self
+    }
+
+    /// Enables compression of the request bodies and sets their `Content-Encoding` header. A body
+    /// that is the same for every request is compressed once, and a templated body for every
+    /// request. Empty bodies are not compressed.
+    ///
+    /// # Parameters
+    ///
+    /// * `compression`: Compression of the request bodies.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LoadTestRunner` with body compression enabled.
+    pub fn with_body_compression(mut self, compression: BodyCompression) -> Self {
+        self.body_compression = Some(compression);
+        self
+    }
+
+    /// Enables streaming the data file or the files of a data directory from disk as chunked
+    /// request bodies, instead of reading them into memory. Streamed bodies are sent as is, so
+    /// they are neither rendered nor compressed. A data file from stdin is still read into memory.
+    ///
+    /// # Parameters
+    ///
+    /// * `streaming`: Chunk size and optional upload bandwidth of the request bodies.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `LoadTestRunner` with body streaming enabled.
+    pub fn with_body_streaming(mut self, streaming: BodyStreaming) -> Self {
+        self.body_streaming = Some(streaming);
+        self
+    }
+
     /// Executes the load test and streams progress updates via a callback.
     ///
     /// This is the main method for running the test. It sends the configured number of requests
@@ -249,19 +848,17 @@ impl LoadTestRunner {
     where
         T: Fn(&LoadTestResult),
     {
-        let body = Self::get_data(body.unwrap_or(Body::Data(Bytes::new()))).await?;
-        let stream = stream::iter(0..self.requests as u64)
-            .map(|i| {
-                let headers = header.clone().unwrap_or_default();
-                let body = body.clone();
-                async move {
-                    let start_time = Instant::now();
-                    let response = self.send_request(method, headers, body).await;
-                    let duration = start_time.elapsed();
-                    (response, duration, i, None)
-                }
-            })
-            .buffer_unordered(self.concurrency as usize);
+        let body = self
+            .prepare_body(body.unwrap_or(Body::Data(Bytes::new())))
+            .await?;
+        let request = self.prepare(&header.unwrap_or_default(), body)?;
+        let request = &request;
+        let stream = self.iterations(self.think_time, move |i, user| async move {
+            let outcome = self
+                .send_prepared_request(method, request, i, None, user.as_ref())
+                .await;
+            (user, vec![outcome])
+        })?;
         self.process_stream(stream, in_progress, output_dir).await
     }
 
@@ -300,37 +897,32 @@ impl LoadTestRunner {
     where
         T: Fn(&LoadTestResult),
     {
-        if method == HttpMethod::Get || method == HttpMethod::Head {
-            bail!("HTTP method '{:?}' not supported", method);
-        }
-        let mut file_names = Self::get_file_names(data_dir).await?;
-        // Sort the file names to make it deterministic.
-        file_names.sort();
-        let mut random = rand::rng();
-        let stream = stream::iter(0..self.requests as u64)
-            .map(|i| {
-                let headers = header.clone().unwrap_or_default();
-                let index = match order {
-                    Order::Sequential => i as usize % file_names.len(),
-                    Order::Random => random.random_range(0..file_names.len()),
-                };
-                let path = &file_names[index];
-                let base_file_name = path.file_stem().map(|f| f.to_owned());
-                async move {
-                    let body = match fs::read(path).await {
-                        Ok(data) => data.into(),
-                        Err(e) => return (Err(e.into()), Duration::default(), i, base_file_name),
-                    };
-                    let start_time = Instant::now();
-                    if method == HttpMethod::Get || method == HttpMethod::Head {
-                        panic!("Unexpected HTTP method '{method:?}'");
-                    }
-                    let response = self.send_request(method, headers, body).await;
-                    let duration = start_time.elapsed();
-                    (response, duration, i, base_file_name)
-                }
-            })
-            .buffer_unordered(self.concurrency as usize);
+        let file_names = self.get_data_source(data_dir, order).await?;
+        let header = header.unwrap_or_default();
+        let file_names = &file_names;
+        let header = &header;
+        let stream = self.iterations(self.think_time, move |i, user| async move {
+            let path = match file_names.pick(i) {
+                Ok(path) => path,
+                Err(e) => return (user, vec![RequestOutcome::error(e, i, None)]),
+            };
+            let base_file_name = path.file_stem().map(|f| f.to_owned());
+            let body = match self.read_data_file(method, path).await {
+                Ok(body) => body,
+                Err(e) => return (user, vec![RequestOutcome::error(e, i, base_file_name)]),
+            };
+            let mut request = match self.prepare(header, body) {
+                Ok(request) => request,
+                Err(e) => return (user, vec![RequestOutcome::error(e, i, base_file_name)]),
+            };
+            request.name = base_file_name
+                .as_ref()
+                .map(|f| f.to_string_lossy().into_owned());
+            let outcome = self
+                .send_prepared_request(method, &request, i, base_file_name, user.as_ref())
+                .await;
+            (user, vec![outcome])
+        })?;
         self.process_stream(stream, in_progress, output_dir).await
     }
 
@@ -366,188 +958,1219 @@ impl LoadTestRunner {
     where
         T: Fn(&LoadTestResult),
     {
-        let file = File::open(manifest_file).await?;
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
-        let mut templates: Vec<RequestTemplate> = Vec::new();
-        while let Some(line) = lines.next_line().await? {
-            let template = serde_json::from_str(&line)?;
-            templates.push(template);
-        }
-        let mut random = rand::rng();
-        let stream = stream::iter(0..self.requests as u64)
-            .map(|i| {
-                let index = match order {
-                    Order::Sequential => i as usize % templates.len(),
-                    Order::Random => random.random_range(0..templates.len()),
+        if let Some(streaming) = self.manifest_streaming(manifest_file, order) {
+            let reader = ManifestReader::open(
+                manifest_file,
+                order,
+                streaming.looping,
+                streaming.block_size,
+            )
+            .await?;
+            let reader = &reader;
+            let stream = self.iterations(self.think_time, move |i, user| async move {
+                let request = match reader.next().await {
+                    Ok(template) => self.prepare_template(&template, None).await,
+                    Err(e) => Err(e),
                 };
-                let template = &templates[index];
-                async move {
-                    let mut headers = HeaderMap::new();
-                    for (name, value) in &template.headers {
-                        let header_name = match HeaderName::from_str(name) {
-                            Ok(name) => name,
-                            Err(e) => {
-                                return (Err(e.into()), Duration::default(), i, None);
-                            }
-                        };
-                        let header_value = match HeaderValue::from_str(value) {
-                            Ok(name) => name,
-                            Err(e) => {
-                                return (Err(e.into()), Duration::default(), i, None);
-                            }
-                        };
-                        headers.insert(header_name, header_value);
+                let outcome = match request {
+                    Ok(request) => {
+                        self.send_prepared_request(method, &request, i, None, user.as_ref())
+                            .await
                     }
-                    let body = if let Some(body) = &template.body {
-                        Bytes::from(body.clone())
-                    } else if let Some(base64_body) = &template.binary_body {
-                        let bytes = match BASE64_STANDARD.decode(base64_body) {
-                            Ok(body) => body,
-                            Err(e) => {
-                                return (Err(e.into()), Duration::default(), i, None);
-                            }
-                        };
-                        Bytes::from(bytes)
-                    } else {
-                        Bytes::new()
-                    };
-                    let start_time = Instant::now();
-                    let response = self.send_request(method, headers, body).await;
-                    let duration = start_time.elapsed();
-                    (response, duration, i, None)
+                    Err(e) => RequestOutcome::error(e, i, None),
+                };
+                (user, vec![outcome])
+            })?;
+            return self.process_stream(stream, in_progress, output_dir).await;
+        }
+        let requests = self.read_manifest(manifest_file, order).await?;
+        let requests = &requests;
+        let stream = self.iterations(self.think_time, move |i, user| async move {
+            let outcome = match requests.pick(i) {
+                Ok(request) => {
+                    self.send_prepared_request(method, request, i, None, user.as_ref())
+                        .await
+                }
+                Err(e) => RequestOutcome::error(e, i, None),
+            };
+            (user, vec![outcome])
+        })?;
+        self.process_stream(stream, in_progress, output_dir).await
+    }
+
+    /// Executes the load test with the URLs of a URL file and streams progress updates via a
+    /// callback.
+    ///
+    /// Every line of the URL file is an absolute URL, or a path relative to the target URL. Empty
+    /// lines and lines that start with `#` are skipped. Every request is sent with the same
+    /// headers and body. After each request completes, it invokes the `in_progress` callback with
+    /// the current, cumulative statistics.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method (GET, POST, etc.) to use.
+    /// * `header`: A `reqwest::header::HeaderMap` containing custom HTTP headers to be sent with
+    ///   each request.
+    /// * `body`: Request body. It can be in-memory byte slice or a file that contains a request
+    ///   body.
+    /// * `url_file`: A URL file, or [`input::STDIN`].
+    /// * `order`: Order to process the URLs of the `url_file`.
+    /// * `output_dir`: Directory to save responses to.
+    /// * `in_progress`: A callback function that is invoked after each request completes.
+    ///   It receives a reference to the `LoadTestResult` struct, allowing for real-time progress
+    ///   reporting.
+    ///
+    /// # Returns
+    ///
+    /// Upon completion of all requests, it returns a `Result` containing the final `LoadTestResult`
+    /// with the complete summary of the test run.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn run_from_url_file<T>(
+        &self,
+        method: HttpMethod,
+        header: Option<HeaderMap>,
+        body: Option<Body>,
+        url_file: &Path,
+        order: Order,
+        output_dir: &Option<PathBuf>,
+        in_progress: T,
+    ) -> Result<LoadTestResult>
+    where
+        T: Fn(&LoadTestResult),
+    {
+        let requests = self
+            .read_url_file(header.unwrap_or_default(), body, url_file, order)
+            .await?;
+        let requests = &requests;
+        let stream = self.iterations(self.think_time, move |i, user| async move {
+            let outcome = match requests.pick(i) {
+                Ok(request) => {
+                    self.send_prepared_request(method, request, i, None, user.as_ref())
+                        .await
+                }
+                Err(e) => RequestOutcome::error(e, i, None),
+            };
+            (user, vec![outcome])
+        })?;
+        self.process_stream(stream, in_progress, output_dir).await
+    }
+
+    /// Executes the load test with the requests of a HAR file and streams progress updates via a
+    /// callback.
+    ///
+    /// The requests are either replayed at load in an order, or at their original relative times.
+    /// After each request completes, it invokes the `in_progress` callback with the current,
+    /// cumulative statistics.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method (GET, POST, etc.) of the entries without a method.
+    /// * `har`: A HAR file.
+    /// * `replay`: How to replay the entries of the `har`.
+    /// * `output_dir`: Directory to save responses to.
+    /// * `in_progress`: A callback function that is invoked after each request completes.
+    ///   It receives a reference to the `LoadTestResult` struct, allowing for real-time progress
+    ///   reporting.
+    ///
+    /// # Returns
+    ///
+    /// Upon completion of all requests, it returns a `Result` containing the final `LoadTestResult`
+    /// with the complete summary of the test run.
+    pub async fn run_from_har<T>(
+        &self,
+        method: HttpMethod,
+        har: &Har,
+        replay: Replay,
+        output_dir: &Option<PathBuf>,
+        in_progress: T,
+    ) -> Result<LoadTestResult>
+    where
+        T: Fn(&LoadTestResult),
+    {
+        self.run_recording(method, har.entries(), replay, output_dir, in_progress)
+            .await
+    }
+
+    /// Executes the load test with the requests of an access log and streams progress updates via
+    /// a callback.
+    ///
+    /// Every request is sent to the target URL plus the path of the request. The requests are
+    /// either replayed at load in an order, or at their original relative times. After each
+    /// request completes, it invokes the `in_progress` callback with the current, cumulative
+    /// statistics.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method (GET, POST, etc.) of the requests without a method.
+    /// * `access_log`: An access log.
+    /// * `replay`: How to replay the requests of the `access_log`.
+    /// * `output_dir`: Directory to save responses to.
+    /// * `in_progress`: A callback function that is invoked after each request completes.
+    ///   It receives a reference to the `LoadTestResult` struct, allowing for real-time progress
+    ///   reporting.
+    ///
+    /// # Returns
+    ///
+    /// Upon completion of all requests, it returns a `Result` containing the final `LoadTestResult`
+    /// with the complete summary of the test run.
+    pub async fn run_from_access_log<T>(
+        &self,
+        method: HttpMethod,
+        access_log: &AccessLog,
+        replay: Replay,
+        output_dir: &Option<PathBuf>,
+        in_progress: T,
+    ) -> Result<LoadTestResult>
+    where
+        T: Fn(&LoadTestResult),
+    {
+        self.run_recording(
+            method,
+            access_log.requests(),
+            replay,
+            output_dir,
+            in_progress,
+        )
+        .await
+    }
+
+    /// Executes the load test with the requests of curl commands and streams progress updates via
+    /// a callback.
+    ///
+    /// This is the main method for running the test. It sends the configured number of requests
+    /// concurrently. After each request completes, it invokes the `in_progress` callback with the
+    /// current, cumulative statistics.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method (GET, POST, etc.) of the commands without a method.
+    /// * `commands`: Curl commands.
+    /// * `order`: Order to process the `commands`.
+    /// * `output_dir`: Directory to save responses to.
+    /// * `in_progress`: A callback function that is invoked after each request completes.
+    ///   It receives a reference to the `LoadTestResult` struct, allowing for real-time progress
+    ///   reporting.
+    ///
+    /// # Returns
+    ///
+    /// Upon completion of all requests, it returns a `Result` containing the final `LoadTestResult`
+    /// with the complete summary of the test run.
+    pub async fn run_from_curl<T>(
+        &self,
+        method: HttpMethod,
+        commands: &[CurlCommand],
+        order: Order,
+        output_dir: &Option<PathBuf>,
+        in_progress: T,
+    ) -> Result<LoadTestResult>
+    where
+        T: Fn(&LoadTestResult),
+    {
+        let recording: Vec<_> = commands
+            .iter()
+            .map(|command| RecordedRequest {
+                request: command.request.clone(),
+                offset: Duration::ZERO,
+            })
+            .collect();
+        self.run_recording(
+            method,
+            &recording,
+            Replay::Order(order),
+            output_dir,
+            in_progress,
+        )
+        .await
+    }
+
+    /// Executes the load test with the operations of an OpenAPI document and streams progress
+    /// updates via a callback.
+    ///
+    /// Every operation is sent to the target URL plus the path of the operation, and the
+    /// operations are picked in an order by their weights. After each request completes, it
+    /// invokes the `in_progress` callback with the current, cumulative statistics.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method (GET, POST, etc.) of the operations without a method.
+    /// * `openapi`: An OpenAPI document.
+    /// * `order`: Order to process the operations of the `openapi`.
+    /// * `validate_responses`: Whether to fail the responses that do not match the declared
+    ///   response schemas.
+    /// * `output_dir`: Directory to save responses to.
+    /// * `in_progress`: A callback function that is invoked after each request completes.
+    ///   It receives a reference to the `LoadTestResult` struct, allowing for real-time progress
+    ///   reporting.
+    ///
+    /// # Returns
+    ///
+    /// Upon completion of all requests, it returns a `Result` containing the final `LoadTestResult`
+    /// with the complete summary of the test run.
+    pub async fn run_from_openapi<T>(
+        &self,
+        method: HttpMethod,
+        openapi: &OpenApi,
+        order: Order,
+        validate_responses: bool,
+        output_dir: &Option<PathBuf>,
+        in_progress: T,
+    ) -> Result<LoadTestResult>
+    where
+        T: Fn(&LoadTestResult),
+    {
+        let mut operations = Vec::new();
+        for operation in openapi.operations() {
+            let request = self
+                .prepare_template(&operation.request, None)
+                .await
+                .context(format!("Invalid operation '{}'", operation.name))?;
+            operations.push((request, operation));
+        }
+        let weights = openapi
+            .operations()
+            .iter()
+            .map(|operation| operation.request.weight.unwrap_or(1))
+            .collect();
+        let operations = DataSource::with_weights(operations, weights, PickMode::from(order))?;
+        let operations = &operations;
+        let stream = self.iterations(self.think_time, move |i, user| async move {
+            let outcome = match operations.pick(i) {
+                Ok((request, operation)) => {
+                    let mut outcome = self
+                        .send_prepared_request(method, request, i, None, user.as_ref())
+                        .await;
+                    if validate_responses && let Ok(response) = outcome.response {
+                        outcome.response = Self::inspect(response, |status, _, body| {
+                            operation.validate(status.as_u16(), body)
+                        })
+                        .await;
+                    }
+                    outcome
+                }
+                Err(e) => RequestOutcome::error(e, i, None),
+            };
+            (user, vec![outcome])
+        })?;
+        self.process_stream(stream, in_progress, output_dir).await
+    }
+
+    /// Replays recorded requests, either at load in an order or at their offsets from the start of
+    /// the recording.
+    async fn run_recording<T>(
+        &self,
+        method: HttpMethod,
+        recording: &[RecordedRequest],
+        replay: Replay,
+        output_dir: &Option<PathBuf>,
+        in_progress: T,
+    ) -> Result<LoadTestResult>
+    where
+        T: Fn(&LoadTestResult),
+    {
+        let mut requests = Vec::new();
+        for (i, recorded) in recording.iter().enumerate() {
+            let request = self
+                .prepare_template(&recorded.request, None)
+                .await
+                .context(format!("Invalid recorded request {}", i + 1))?;
+            requests.push(request);
+        }
+        let offsets: Vec<_> = recording.iter().map(|recorded| recorded.offset).collect();
+        let speed = match replay {
+            Replay::Order(order) => {
+                let requests = DataSource::new(requests, PickMode::from(order))?;
+                let requests = &requests;
+                let stream = self.iterations(self.think_time, move |i, user| async move {
+                    let outcome = match requests.pick(i) {
+                        Ok(request) => {
+                            self.send_prepared_request(method, request, i, None, user.as_ref())
+                                .await
+                        }
+                        Err(e) => RequestOutcome::error(e, i, None),
+                    };
+                    (user, vec![outcome])
+                })?;
+                return self.process_stream(stream, in_progress, output_dir).await;
+            }
+            Replay::Timing(speed) if speed > 0.0 && speed.is_finite() => speed,
+            Replay::Timing(speed) => bail!("Invalid replay speed '{speed}'"),
+        };
+        if requests.is_empty() {
+            bail!("There are no requests to replay");
+        }
+        // The recording starts over after the average gap between its requests, so that the last
+        // request of a loop and the first request of the next loop are not sent at the same time.
+        let span = offsets.last().copied().unwrap_or_default();
+        let period = match offsets.len() {
+            1 => span,
+            len => span + span / (len - 1) as u32,
+        };
+        let (requests, offsets) = (&requests, &offsets);
+        let start = tokio::time::Instant::now();
+        let stream = self.iterations(self.think_time, move |i, user| async move {
+            let n = requests.len() as u64;
+            let offset = u32::try_from(i / n)
+                .ok()
+                .and_then(|loops| period.checked_mul(loops))
+                .and_then(|offset| offset.checked_add(offsets[(i % n) as usize]));
+            let Some(offset) = offset else {
+                let e = anyhow::anyhow!("Replay offset of request {} is too large", i + 1);
+                return (user, vec![RequestOutcome::error(e, i, None)]);
+            };
+            tokio::time::sleep_until(start + offset.div_f64(speed)).await;
+            let request = &requests[(i % n) as usize];
+            let outcome = self
+                .send_prepared_request(method, request, i, None, user.as_ref())
+                .await;
+            (user, vec![outcome])
+        })?;
+        self.process_stream(stream, in_progress, output_dir).await
+    }
+
+    /// Executes the load test with a scenario and streams progress updates via a callback.
+    ///
+    /// Every iteration runs the steps of the scenario in order, and the values extracted from the
+    /// response of a step are available as `{{name}}` variables to the later steps. A failed step,
+    /// or a step whose values cannot be extracted, ends the iteration. The `in_progress` callback
+    /// is invoked after each request completes.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method (GET, POST, etc.) of the steps without a `method`.
+    /// * `scenario`: A scenario.
+    /// * `output_dir`: Directory to save responses to.
+    /// * `in_progress`: A callback function that is invoked after each request completes.
+    ///   It receives a reference to the `LoadTestResult` struct, allowing for real-time progress
+    ///   reporting.
+    ///
+    /// # Returns
+    ///
+    /// Upon completion of all iterations, it returns a `Result` containing the final
+    /// `LoadTestResult` with the complete summary of the test run.
+    pub async fn run_scenario<T>(
+        &self,
+        method: HttpMethod,
+        scenario: &Scenario,
+        output_dir: &Option<PathBuf>,
+        in_progress: T,
+    ) -> Result<LoadTestResult>
+    where
+        T: Fn(&LoadTestResult),
+    {
+        let mut steps = Vec::new();
+        for (i, step) in scenario.steps().iter().enumerate() {
+            let scope = scenario.variables_before(i);
+            let request = self
+                .prepare_template(&step.request, Some(&scope))
+                .await
+                .context(format!("Invalid step {}", i + 1))?;
+            steps.push((request, step));
+        }
+        let steps = &steps;
+        // The think time of the last step is the pause before the next iteration.
+        let think_time = scenario
+            .steps()
+            .last()
+            .and_then(|step| step.think_time)
+            .or(self.think_time);
+        let stream = self.iterations(think_time, move |i, user| async move {
+            self.run_steps(method, steps, i, user).await
+        })?;
+        self.process_stream(stream, in_progress, output_dir).await
+    }
+
+    /// Runs the steps of a scenario for a single iteration.
+    async fn run_steps(
+        &self,
+        method: HttpMethod,
+        steps: &[(PreparedRequest, &Step)],
+        iteration: u64,
+        mut user: Option<VirtualUser>,
+    ) -> (Option<VirtualUser>, Vec<RequestOutcome>) {
+        let mut variables = match &mut user {
+            Some(user) => std::mem::take(&mut user.variables),
+            None => HashMap::new(),
+        };
+        if let Some(feeder) = &self.feeder {
+            match feeder.pick(iteration) {
+                Ok(row) => variables.extend(row.iter().map(|(k, v)| (k.clone(), v.clone()))),
+                Err(e) => return (user, vec![RequestOutcome::error(e, iteration, None)]),
+            }
+        }
+        let mut outcomes = Vec::new();
+        for (i, (request, step)) in steps.iter().enumerate() {
+            let base_file_name = Some(OsString::from(format!("step{}", i + 1)));
+            let row = Row::new(variables.clone());
+            let mut outcome = self
+                .send_prepared_request_with(
+                    method,
+                    request,
+                    iteration,
+                    base_file_name,
+                    Some(&row),
+                    user.as_ref(),
+                )
+                .await;
+            if !step.extract.is_empty()
+                && let Ok(response) = outcome.response
+            {
+                outcome.response = Self::extract(response, &step.extract, &mut variables).await;
+            }
+            let failed = outcome.response.is_err();
+            outcomes.push(outcome);
+            if failed || i == steps.len() - 1 {
+                break;
+            }
+            if let Some(think_time) = step.think_time.or(self.think_time) {
+                tokio::time::sleep(think_time.sample()).await;
+            }
+        }
+        if let Some(user) = &mut user {
+            user.variables = variables;
+        }
+        (user, outcomes)
+    }
+
+    /// Runs every iteration, either as independent requests with up to `concurrency` at a time, or
+    /// by `concurrency` users that each run one iteration after another. Users are used if virtual
+    /// users, a think time or iteration pacing is enabled, and they pause between two iterations
+    /// after the outcomes of the previous iteration are emitted.
+    fn iterations<'a, F, Fut>(
+        &'a self,
+        think_time: Option<ThinkTime>,
+        run: F,
+    ) -> Result<BoxStream<'a, RequestOutcome>>
+    where
+        F: Fn(u64, Option<VirtualUser>) -> Fut + Clone + Send + 'a,
+        Fut: Future<Output = (Option<VirtualUser>, Vec<RequestOutcome>)> + Send + 'a,
+    {
+        if self.virtual_users.is_none() && think_time.is_none() && self.pacing.is_none() {
+            return Ok(stream::iter(0..self.requests as u64)
+                .map(move |i| run(i, None))
+                .buffer_unordered(self.concurrency as usize)
+                .flat_map(|(_, outcomes)| stream::iter(outcomes))
+                .boxed());
+        }
+        let next_iteration = Arc::new(AtomicU64::new(0));
+        let mut users = Vec::new();
+        for _ in 0..self.concurrency {
+            let user = self
+                .virtual_users
+                .map(|virtual_users| self.new_virtual_user(virtual_users))
+                .transpose()?;
+            let next_iteration = next_iteration.clone();
+            let run = run.clone();
+            let requests = self.requests as u64;
+            let pacing = self.pacing;
+            let user_stream = stream::unfold((user, None), move |(user, next_start)| {
+                let next_iteration = next_iteration.clone();
+                let run = run.clone();
+                async move {
+                    if next_iteration.load(Ordering::Relaxed) >= requests {
+                        return None;
+                    }
+                    if let Some(next_start) = next_start {
+                        tokio::time::sleep_until(next_start).await;
+                    }
+                    let i = next_iteration.fetch_add(1, Ordering::Relaxed);
+                    if i >= requests {
+                        return None;
+                    }
+                    let start = tokio::time::Instant::now();
+                    let (user, outcomes) = run(i, user).await;
+                    let mut next_start = tokio::time::Instant::now()
+                        + think_time.map_or(Duration::ZERO, |think_time| think_time.sample());
+                    if let Some(pacing) = pacing {
+                        next_start = next_start.max(start + pacing);
+                    }
+                    Some((stream::iter(outcomes), (user, Some(next_start))))
+                }
+            })
+            .flatten();
+            users.push(user_stream.boxed());
+        }
+        Ok(stream::select_all(users).boxed())
+    }
+
+    fn new_virtual_user(&self, virtual_users: VirtualUsers) -> Result<VirtualUser> {
+        let jar = Arc::new(Jar::default());
+        Ok(if virtual_users.shared_connections {
+            VirtualUser {
+                client: self.client.clone(),
+                cookies: Some(jar),
+                variables: HashMap::new(),
+            }
+        } else {
+            VirtualUser {
+                client: Self::client_builder(&self.tls)
+                    .cookie_provider(jar)
+                    .build()?,
+                cookies: None,
+                variables: HashMap::new(),
+            }
+        })
+    }
+
+    /// Extracts variables from a response.
+    async fn extract(
+        response: Response,
+        extract: &[(String, Extractor)],
+        variables: &mut HashMap<String, String>,
+    ) -> Result<Response> {
+        Self::inspect(response, |_, headers, body| {
+            for (name, extractor) in extract {
+                let value = extractor
+                    .extract(headers, body)
+                    .context(format!("Failed to extract '{name}' from the response"))?;
+                variables.insert(name.clone(), value);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Inspects the status, the headers and the body of a response. The body of the response is
+    /// read, so a new response with the same status, headers and body is returned.
+    async fn inspect<F>(response: Response, inspect: F) -> Result<Response>
+    where
+        F: FnOnce(StatusCode, &HeaderMap, &Bytes) -> Result<()>,
+    {
+        let status = response.status();
+        let mut builder = http::Response::builder()
+            .status(status)
+            .version(response.version());
+        if let Some(headers) = builder.headers_mut() {
+            *headers = response.headers().clone();
+        }
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        inspect(status, &headers, &body)?;
+        Ok(Response::from(builder.body(body)?))
+    }
+
+    /// Executes a single request for debugging.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method (GET, POST, etc.) to use.
+    /// * `header`: A `reqwest::header::HeaderMap` containing custom HTTP headers to be sent with
+    ///   each request.
+    /// * `body`: Request body. It can be in-memory byte slice or a file that contains a request
+    ///   body.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing `reqwest::Response`.
+    pub async fn debug(
+        &self,
+        method: HttpMethod,
+        header: Option<HeaderMap>,
+        body: Option<Body>,
+    ) -> Result<Response> {
+        let body = self
+            .prepare_body(body.unwrap_or(Body::Data(Bytes::new())))
+            .await?;
+        let request = self.prepare(&header.unwrap_or_default(), body)?;
+        self.debug_prepared_request(method, &request).await
+    }
+
+    /// Executes a single request with a request body from a file in a directory for debugging.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method (GET, POST, etc.) to use.
+    /// * `header`: A `reqwest::header::HeaderMap` containing custom HTTP headers to be sent with
+    ///   each request.
+    /// * `data_dir`: Directory of files to use as request bodies.
+    /// * `order`: Order to process files from the `data_dir`.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing `reqwest::Response`.
+    pub async fn debug_from_dir(
+        &self,
+        method: HttpMethod,
+        header: Option<HeaderMap>,
+        data_dir: &PathBuf,
+        order: Order,
+    ) -> Result<Response> {
+        let file_names = self.get_data_source(data_dir, order).await?;
+        let body = self.read_data_file(method, file_names.pick(0)?).await?;
+        let request = self.prepare(&header.unwrap_or_default(), body)?;
+        self.debug_prepared_request(method, &request).await
+    }
+
+    /// Executes the load test with a request manifest file for debugging.
+    ///
+    /// This is the main method for running the test. It sends the configured number of requests
+    /// concurrently to the target URL. After each request completes, it invokes the `in_progress`
+    /// callback with the current, cumulative statistics.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method (GET, POST, etc.) to use.
+    /// * `manifest_file`: A manifest file.
+    /// * `order`: Order to process request from the `manifest_file`.
+    ///
+    /// # Returns
+    ///
+    /// Upon completion of all requests, it returns a `Result` containing the final `LoadTestResult`
+    /// with the complete summary of the test run.
+    pub async fn debug_from_manifest(
+        &self,
+        method: HttpMethod,
+        manifest_file: &PathBuf,
+        order: Order,
+    ) -> Result<Response> {
+        if let Some(streaming) = self.manifest_streaming(manifest_file, order) {
+            let reader =
+                ManifestReader::open(manifest_file, order, false, streaming.block_size).await?;
+            let request = self.prepare_template(&reader.next().await?, None).await?;
+            return self.debug_prepared_request(method, &request).await;
+        }
+        let requests = self.read_manifest(manifest_file, order).await?;
+        self.debug_prepared_request(method, requests.pick(0)?).await
+    }
+
+    /// Executes a single request with a URL from a URL file for debugging.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method (GET, POST, etc.) to use.
+    /// * `header`: A `reqwest::header::HeaderMap` containing custom HTTP headers to be sent with
+    ///   the request.
+    /// * `body`: Request body. It can be in-memory byte slice or a file that contains a request
+    ///   body.
+    /// * `url_file`: A URL file, or [`input::STDIN`].
+    /// * `order`: Order to process the URLs of the `url_file`.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing `reqwest::Response`.
+    pub async fn debug_from_url_file(
+        &self,
+        method: HttpMethod,
+        header: Option<HeaderMap>,
+        body: Option<Body>,
+        url_file: &Path,
+        order: Order,
+    ) -> Result<Response> {
+        let requests = self
+            .read_url_file(header.unwrap_or_default(), body, url_file, order)
+            .await?;
+        self.debug_prepared_request(method, requests.pick(0)?).await
+    }
+
+    /// Reads a URL file and parses the request of every URL once.
+    async fn read_url_file(
+        &self,
+        headers: HeaderMap,
+        body: Option<Body>,
+        url_file: &Path,
+        order: Order,
+    ) -> Result<DataSource<PreparedRequest>> {
+        let body = body.unwrap_or(Body::Data(Bytes::new()));
+        if input::is_stdin(url_file)
+            && matches!(&body, Body::DataFile(data_file) if input::is_stdin(data_file))
+        {
+            bail!("URL file and data file cannot both be read from stdin");
+        }
+        // The body is parsed once and shared by the requests to every URL.
+        let template = self.prepare(&headers, self.prepare_body(body).await?)?;
+        let mut lines = input::lines(url_file).await?;
+        let mut requests = Vec::new();
+        let mut line_number = 0;
+        while let Some(line) = lines.next_line().await? {
+            line_number += 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let is_absolute =
+                Url::parse(line).is_ok_and(|url| url.scheme() == "http" || url.scheme() == "https");
+            let url = if is_absolute {
+                line.to_owned()
+            } else {
+                format!(
+                    "{}/{}",
+                    self.url.trim_end_matches('/'),
+                    line.trim_start_matches('/')
+                )
+            };
+            let url_template = self.template(&url, None).context(format!(
+                "Invalid URL on line {line_number} of URL file '{}'",
+                url_file.display()
+            ))?;
+            requests.push(PreparedRequest {
+                url: url_template,
+                endpoint: Self::endpoint(&url),
+                ..template.clone()
+            });
+        }
+        DataSource::new(requests, PickMode::from(order))
+            .context(format!("URL file '{}' is empty", url_file.display()))
+    }
+
+    /// Returns how to read a manifest file lazily, if at all. A manifest from stdin is always read
+    /// lazily, so that its requests are sent while it is still being generated. Its lines are read
+    /// one at a time for the sequential order, and in blocks of [`DEFAULT_BLOCK_SIZE`] lines
+    /// otherwise.
+    fn manifest_streaming(&self, manifest_file: &Path, order: Order) -> Option<ManifestStreaming> {
+        self.manifest_streaming.or_else(|| {
+            input::is_stdin(manifest_file).then_some(ManifestStreaming {
+                looping: false,
+                block_size: match order {
+                    Order::Sequential => 1,
+                    _ => DEFAULT_BLOCK_SIZE,
+                },
+            })
+        })
+    }
+
+    /// Reads a manifest file and parses the templates of every request once.
+    async fn read_manifest(
+        &self,
+        manifest_file: &PathBuf,
+        order: Order,
+    ) -> Result<DataSource<PreparedRequest>> {
+        let mut lines = input::lines(manifest_file).await?;
+        let mut requests: Vec<PreparedRequest> = Vec::new();
+        let mut weights = Vec::new();
+        let mut line_number = 0;
+        while let Some(line) = lines.next_line().await? {
+            line_number += 1;
+            let template: RequestTemplate = serde_json::from_str(&line)?;
+            let request = self
+                .prepare_template(&template, None)
+                .await
+                .context(format!(
+                    "Invalid request on line {line_number} of manifest file '{}'",
+                    manifest_file.display()
+                ))?;
+            requests.push(request);
+            weights.push(template.weight.unwrap_or(1));
+        }
+        DataSource::with_weights(requests, weights, PickMode::from(order)).context(format!(
+            "Manifest file '{}' is empty",
+            manifest_file.display()
+        ))
+    }
+
+    /// Reads a file of a data directory, unless it is streamed from disk or the method does not
+    /// send a body. GET and HEAD requests are still named after the file.
+    async fn read_data_file(&self, method: HttpMethod, path: &Path) -> Result<BodyTemplate> {
+        if method == HttpMethod::Get || method == HttpMethod::Head {
+            return Ok(BodyTemplate::Static(Bytes::new()));
+        }
+        if self.body_streaming.is_some() {
+            return Ok(BodyTemplate::File(path.to_owned()));
+        }
+        Ok(BodyTemplate::Static(fs::read(path).await?.into()))
+    }
+
+    async fn get_data_source(
+        &self,
+        data_dir: &PathBuf,
+        order: Order,
+    ) -> Result<DataSource<PathBuf>> {
+        let mut file_names = Self::get_file_names(data_dir).await?;
+        // Sort the file names to make it deterministic.
+        file_names.sort();
+        DataSource::new(file_names, PickMode::from(order))
+            .context(format!("Data directory '{}' is empty", data_dir.display()))
+    }
+
+    /// Parses the URL, the headers and the body of a request into templates if templating is
+    /// enabled.
+    fn prepare(&self, headers: &HeaderMap, body: BodyTemplate) -> Result<PreparedRequest> {
+        let mut headers = headers
+            .iter()
+            .map(|(name, value)| {
+                // Values are only parsed if templating is enabled, so that values that are not
+                // valid UTF-8 are sent as is otherwise.
+                let value = if self.templating {
+                    let value = str::from_utf8(value.as_bytes())?;
+                    HeaderTemplate::Template(self.template(value, None)?)
+                } else {
+                    HeaderTemplate::Raw(value.clone())
+                };
+                Ok((name.to_string(), value))
+            })
+            .collect::<Result<_>>()?;
+        Self::set_content_type(&mut headers, &body);
+        Ok(PreparedRequest {
+            name: None,
+            method: None,
+            url: self.template(&self.url, None)?,
+            query: Vec::new(),
+            headers,
+            compressed_body: self.compress_static(&body)?,
+            body,
+            endpoint: Self::endpoint(&self.url),
+        })
+    }
+
+    /// Parses a request of a manifest file, or a step of a scenario if `scope` contains the
+    /// variables extracted by the previous steps. Steps are always parsed as templates.
+    async fn prepare_template(
+        &self,
+        template: &RequestTemplate,
+        scope: Option<&BTreeSet<String>>,
+    ) -> Result<PreparedRequest> {
+        let mut headers = template
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = HeaderTemplate::Template(self.template(value, scope)?);
+                Ok((name.clone(), value))
+            })
+            .collect::<Result<_>>()?;
+        let body = if let Some(body) = &template.body {
+            self.body_template(Bytes::from(body.clone()), scope)?
+        } else if let Some(base64_body) = &template.binary_body {
+            BodyTemplate::Base64(base64_body.clone())
+        } else if !template.form.is_empty() {
+            let fields = template
+                .form
+                .iter()
+                .map(|(name, value)| FormField::new(name, value))
+                .collect::<Result<Vec<_>>>()?;
+            self.multipart_template(&fields, scope).await?
+        } else if !template.form_urlencoded.is_empty() {
+            let fields = template
+                .form_urlencoded
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), self.template(value, scope)?)))
+                .collect::<Result<_>>()?;
+            BodyTemplate::UrlEncoded(fields)
+        } else {
+            BodyTemplate::Static(Bytes::new())
+        };
+        Self::set_content_type(&mut headers, &body);
+        let method = template
+            .method
+            .as_deref()
+            .map(HttpMethod::from_str)
+            .transpose()?;
+        let url = match (&template.url, &template.path) {
+            (Some(url), _) => url.clone(),
+            (None, Some(path)) => format!(
+                "{}/{}",
+                self.url.trim_end_matches('/'),
+                path.trim_start_matches('/')
+            ),
+            (None, None) => self.url.clone(),
+        };
+        let query = template
+            .query
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), self.template(value, scope)?)))
+            .collect::<Result<_>>()?;
+        Ok(PreparedRequest {
+            name: template.name.clone(),
+            method,
+            url: self.template(&url, scope)?,
+            query,
+            headers,
+            compressed_body: self.compress_static(&body)?,
+            body,
+            endpoint: Self::endpoint(&url),
+        })
+    }
+
+    fn template(&self, s: &str, scope: Option<&BTreeSet<String>>) -> Result<Template> {
+        if !self.templating && scope.is_none() {
+            return Ok(Template::literal(s));
+        }
+        let template = Template::parse(s)?;
+        for name in template.variables() {
+            let in_feeder = self
+                .feeder
+                .as_ref()
+                .is_some_and(|feeder| feeder.columns().contains(name));
+            let in_scope = scope.is_some_and(|scope| scope.contains(name));
+            if !in_feeder && !in_scope {
+                bail!("Unknown template variable '{name}'");
+            }
+        }
+        Ok(template)
+    }
+
+    /// Creates the values to render the templates of a request with. The `variables` override the
+    /// row that is otherwise picked from the feeder.
+    fn render_context(
+        &self,
+        context: &RequestContext,
+        variables: Option<&Row>,
+    ) -> Result<RenderContext> {
+        let variables = match (variables, &self.feeder) {
+            (Some(variables), _) => Some(variables.clone()),
+            (None, Some(feeder)) => Some(feeder.pick(context.iteration)?.clone()),
+            (None, None) => None,
+        };
+        Ok(RenderContext {
+            seq: context.iteration + 1,
+            variables,
+        })
+    }
+
+    /// Parses the body of a load test, reading a data file or the files of a form once.
+    async fn prepare_body(&self, body: Body) -> Result<BodyTemplate> {
+        match body {
+            Body::Multipart(fields) => self.multipart_template(&fields, None).await,
+            Body::UrlEncoded(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, value)| Ok((name.clone(), self.template(value, None)?)))
+                    .collect::<Result<_>>()?;
+                Ok(BodyTemplate::UrlEncoded(fields))
+            }
+            Body::DataFile(data_file)
+                if self.body_streaming.is_some() && !input::is_stdin(&data_file) =>
+            {
+                if !data_file.is_file() {
+                    bail!(
+                        "Data file '{}' does not exist or is not a file",
+                        data_file.display()
+                    );
                 }
-            })
-            .buffer_unordered(self.concurrency as usize);
-        self.process_stream(stream, in_progress, output_dir).await
+                Ok(BodyTemplate::File(data_file))
+            }
+            body => self.body_template(Self::get_data(body).await?, None),
+        }
     }
 
-    /// Executes a single request for debugging.
-    ///
-    /// # Parameters
-    ///
-    /// * `method`: HTTP method (GET, POST, etc.) to use.
-    /// * `header`: A `reqwest::header::HeaderMap` containing custom HTTP headers to be sent with
-    ///   each request.
-    /// * `body`: Request body. It can be in-memory byte slice or a file that contains a request
-    ///   body.
-    ///
-    /// # Returns
-    ///
-    /// Returns a `Result` containing `reqwest::Response`.
-    pub async fn debug(
+    async fn multipart_template(
         &self,
-        method: HttpMethod,
-        header: Option<HeaderMap>,
-        body: Option<Body>,
-    ) -> Result<Response> {
-        let headers = header.unwrap_or_default();
-        let body = Self::get_data(body.unwrap_or(Body::Data(Bytes::new()))).await?;
-        self.send_request(method, headers, body).await
+        fields: &[FormField],
+        scope: Option<&BTreeSet<String>>,
+    ) -> Result<BodyTemplate> {
+        let multipart = Multipart::new(fields, |value| self.template(value, scope)).await?;
+        Ok(BodyTemplate::Multipart(multipart))
     }
 
-    /// Executes a single request with a request body from a file in a directory for debugging.
-    ///
-    /// # Parameters
-    ///
-    /// * `method`: HTTP method (GET, POST, etc.) to use.
-    /// * `header`: A `reqwest::header::HeaderMap` containing custom HTTP headers to be sent with
-    ///   each request.
-    /// * `data_dir`: Directory of files to use as request bodies.
-    /// * `order`: Order to process files from the `data_dir`.
+    /// Compresses a body that is the same for every request, if body compression is enabled.
+    fn compress_static(&self, body: &BodyTemplate) -> Result<Option<Bytes>> {
+        match (self.body_compression, body.render_static()) {
+            (Some(compression), Some(body)) if !body.is_empty() => {
+                Ok(Some(compression.compress(&body)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Compresses a rendered body if body compression is enabled, and sets its `Content-Encoding`
+    /// header.
     ///
     /// # Returns
     ///
-    /// Returns a `Result` containing `reqwest::Response`.
-    pub async fn debug_from_dir(
+    /// The body to send, and its size after and before compression.
+    fn compress(
         &self,
-        method: HttpMethod,
-        header: Option<HeaderMap>,
-        data_dir: &PathBuf,
-        order: Order,
-    ) -> Result<Response> {
-        if method == HttpMethod::Get || method == HttpMethod::Head {
-            bail!("HTTP method '{:?}' not supported", method);
-        }
-        let mut file_names = Self::get_file_names(data_dir).await?;
-        // Sort the file names to make it deterministic.
-        file_names.sort();
-        let headers = header.unwrap_or_default();
-        let mut random = rand::rng();
-        let index = match order {
-            Order::Sequential => 0,
-            Order::Random => random.random_range(0..file_names.len()),
+        request: &PreparedRequest,
+        headers: &mut HeaderMap,
+        body: Bytes,
+    ) -> Result<(Bytes, (u64, u64))> {
+        let uncompressed = body.len() as u64;
+        let Some(compression) = self.body_compression.filter(|_| !body.is_empty()) else {
+            return Ok((body, (uncompressed, uncompressed)));
         };
-        let body = fs::read(&file_names[index]).await?.into();
-        if method == HttpMethod::Get || method == HttpMethod::Head {
-            panic!("Unexpected HTTP method '{method:?}'");
+        let body = match &request.compressed_body {
+            Some(compressed_body) => compressed_body.clone(),
+            None => compression.compress(&body)?,
+        };
+        headers.insert(
+            CONTENT_ENCODING,
+            HeaderValue::from_static(compression.content_encoding()),
+        );
+        Ok((body.clone(), (body.len() as u64, uncompressed)))
+    }
+
+    /// Sets the `Content-Type` header of a form body. A multipart body replaces any
+    /// `Content-Type` header, because its boundary must match the body.
+    fn set_content_type(headers: &mut Vec<(String, HeaderTemplate)>, body: &BodyTemplate) {
+        let Some(content_type) = body.content_type() else {
+            return;
+        };
+        let is_content_type = |name: &str| name.eq_ignore_ascii_case("content-type");
+        if matches!(body, BodyTemplate::Multipart(_)) {
+            headers.retain(|(name, _)| !is_content_type(name));
+        } else if headers.iter().any(|(name, _)| is_content_type(name)) {
+            return;
         }
-        self.send_request(method, headers, body).await
+        headers.push((
+            "Content-Type".to_owned(),
+            HeaderTemplate::Template(Template::literal(&content_type)),
+        ));
     }
 
-    /// Executes the load test with a request manifest file for debugging.
-    ///
-    /// This is the main method for running the test. It sends the configured number of requests
-    /// concurrently to the target URL. After each request completes, it invokes the `in_progress`
-    /// callback with the current, cumulative statistics.
-    ///
-    /// # Parameters
-    ///
-    /// * `method`: HTTP method (GET, POST, etc.) to use.
-    /// * `manifest_file`: A manifest file.
-    /// * `order`: Order to process request from the `manifest_file`.
-    ///
-    /// # Returns
-    ///
-    /// Upon completion of all requests, it returns a `Result` containing the final `LoadTestResult`
-    /// with the complete summary of the test run.
-    pub async fn debug_from_manifest(
+    fn body_template(&self, body: Bytes, scope: Option<&BTreeSet<String>>) -> Result<BodyTemplate> {
+        if (self.templating || scope.is_some())
+            && let Ok(body) = str::from_utf8(&body)
+        {
+            let template = self.template(body, scope)?;
+            if !template.is_static() {
+                return Ok(BodyTemplate::Template(template));
+            }
+        }
+        Ok(BodyTemplate::Static(body))
+    }
+
+    async fn send_prepared_request(
         &self,
         method: HttpMethod,
-        manifest_file: &PathBuf,
-        order: Order,
-    ) -> Result<Response> {
-        let file = File::open(manifest_file).await?;
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
-        let mut templates: Vec<RequestTemplate> = Vec::new();
-        while let Some(line) = lines.next_line().await? {
-            let template = serde_json::from_str(&line)?;
-            templates.push(template);
-        }
-        let mut random = rand::rng();
-        let index = match order {
-            Order::Sequential => 0,
-            Order::Random => random.random_range(0..templates.len()),
+        request: &PreparedRequest,
+        iteration: u64,
+        base_file_name: Option<OsString>,
+        user: Option<&VirtualUser>,
+    ) -> RequestOutcome {
+        self.send_prepared_request_with(method, request, iteration, base_file_name, None, user)
+            .await
+    }
+
+    async fn send_prepared_request_with(
+        &self,
+        method: HttpMethod,
+        request: &PreparedRequest,
+        iteration: u64,
+        base_file_name: Option<OsString>,
+        variables: Option<&Row>,
+        user: Option<&VirtualUser>,
+    ) -> RequestOutcome {
+        let method = request.method.unwrap_or(method);
+        let mut context = self.new_context(method, iteration);
+        context.name = Some(
+            request
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{method} {}", request.endpoint)),
+        );
+        context.endpoint = Some(request.endpoint.clone());
+        let rendered = self.render_request(request, &mut context, variables).await;
+        let (url, headers, body) = match rendered {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                return RequestOutcome {
+                    response: Err(e),
+                    duration: Duration::default(),
+                    base_file_name,
+                    context,
+                };
+            }
         };
-        let template = &templates[index];
-        let mut headers = HeaderMap::new();
-        for (name, value) in &template.headers {
-            headers.insert(HeaderName::from_str(name)?, HeaderValue::from_str(value)?);
+        // The span starts when the request is sent rather than when its context is created, so that
+        // rendering the request and reading its body are not part of the span.
+        if let Some(trace) = &mut context.trace {
+            trace.start_time = SystemTime::now();
         }
-        let body = if let Some(body) = &template.body {
-            Bytes::from(body.clone())
-        } else if let Some(base64_body) = &template.binary_body {
-            Bytes::from(BASE64_STANDARD.decode(base64_body)?)
+        let start_time = Instant::now();
+        let response = self
+            .send_request(method, &url, headers, body, &context, user)
+            .await;
+        let duration = start_time.elapsed();
+        RequestOutcome {
+            response,
+            duration,
+            base_file_name,
+            context,
+        }
+    }
+
+    /// Renders a request and opens its body, which is compressed, or streamed from disk for a
+    /// file.
+    async fn render_request(
+        &self,
+        request: &PreparedRequest,
+        context: &mut RequestContext,
+        variables: Option<&Row>,
+    ) -> Result<(String, HeaderMap, reqwest::Body)> {
+        let render_context = self.render_context(context, variables)?;
+        let (url, mut headers, body) = request.render(&render_context)?;
+        let (body, body_size) = if let BodyTemplate::File(path) = &request.body {
+            let streaming = self.body_streaming.unwrap_or_default();
+            let (body, len) = streaming.open(path).await?;
+            (body, (len, len))
         } else {
-            Bytes::new()
+            let (body, body_size) = self.compress(request, &mut headers, body)?;
+            (body.into(), body_size)
         };
-        self.send_request(method, headers, body).await
+        context.body_size = body_size;
+        Ok((url, headers, body))
+    }
+
+    async fn debug_prepared_request(
+        &self,
+        method: HttpMethod,
+        request: &PreparedRequest,
+    ) -> Result<Response> {
+        let method = request.method.unwrap_or(method);
+        let mut context = self.new_context(method, 0);
+        let (url, headers, body) = self.render_request(request, &mut context, None).await?;
+        self.send_request(method, &url, headers, body, &context, None)
+            .await
     }
 
     async fn send_request(
         &self,
         method: HttpMethod,
-        headers: HeaderMap,
-        body: Bytes,
+        url: &str,
+        mut headers: HeaderMap,
+        body: reqwest::Body,
+        context: &RequestContext,
+        user: Option<&VirtualUser>,
     ) -> Result<Response> {
-        match method {
-            HttpMethod::Get => self.get(headers, true).await,
-            HttpMethod::Head => self.head(headers, true).await,
-            HttpMethod::Post => self.post(headers, body, true).await,
-            HttpMethod::Put => self.put(headers, body, true).await,
-            HttpMethod::Delete => self.delete(headers, body, true).await,
-            HttpMethod::Patch => self.patch(headers, body, true).await,
+        if let Some(trace) = &context.trace {
+            headers.insert(
+                HeaderName::from_static("traceparent"),
+                HeaderValue::from_str(&trace.traceparent())?,
+            );
+        }
+        if let (Some((header, ..)), Some(request_id)) = (&self.request_id, &context.request_id) {
+            headers.insert(header.clone(), HeaderValue::from_str(request_id)?);
+        }
+        let cookies = user.and_then(|user| user.cookies.as_ref());
+        if let Some(cookies) = cookies
+            && let Some(cookie) = cookies.cookies(&Url::parse(url)?)
+        {
+            headers.insert(COOKIE, cookie);
+        }
+        let client = user.map_or(&self.client, |user| &user.client);
+        // The cookies are stored before the status is checked, so that the cookies of an error
+        // response are kept like the cookie store of a client does.
+        let response = match method {
+            HttpMethod::Get => self.get(client, url, headers, false).await,
+            HttpMethod::Head => self.head(client, url, headers, false).await,
+            HttpMethod::Post => self.post(client, url, headers, body, false).await,
+            HttpMethod::Put => self.put(client, url, headers, body, false).await,
+            HttpMethod::Delete => self.delete(client, url, headers, body, false).await,
+            HttpMethod::Patch => self.patch(client, url, headers, body, false).await,
+        }?;
+        if let Some(cookies) = cookies {
+            let mut set_cookies = response.headers().get_all(SET_COOKIE).iter();
+            cookies.set_cookies(&mut set_cookies, response.url());
+        }
+        Ok(response.error_for_status()?)
+    }
+
+    fn new_context(&self, method: HttpMethod, iteration: u64) -> RequestContext {
+        let trace = self.trace_sample_ratio.map(|sample_ratio| {
+            let sampled = rand::rng().random_bool(sample_ratio);
+            TraceContext::new(method, sampled)
+        });
+        let request_id = self
+            .request_id
+            .as_ref()
+            .map(|(_, format, run_id)| match format {
+                RequestIdFormat::Uuid => Uuid::new_v4().to_string(),
+                RequestIdFormat::Iteration => format!("{run_id}-{}", iteration + 1),
+            });
+        RequestContext {
+            iteration,
+            name: None,
+            endpoint: None,
+            trace,
+            request_id,
+            body_size: (0, 0),
         }
     }
 
@@ -574,6 +2197,9 @@ impl LoadTestRunner {
     async fn get_data(body: Body) -> Result<Bytes> {
         match body {
             Body::Data(data) => Ok(data),
+            Body::DataFile(data_file) if input::is_stdin(&data_file) => {
+                Ok(input::read(&data_file).await?.into())
+            }
             Body::DataFile(data_file) => {
                 if !data_file.is_file() {
                     bail!(
@@ -584,6 +2210,7 @@ impl LoadTestRunner {
                 let data = fs::read(data_file).await?;
                 Ok(data.into())
             }
+            Body::Multipart(_) | Body::UrlEncoded(_) => bail!("Form body cannot be read as data"),
         }
     }
 
@@ -605,14 +2232,7 @@ impl LoadTestRunner {
         output_dir: &Option<PathBuf>,
     ) -> Result<LoadTestResult>
     where
-        S: Stream<
-                Item = (
-                    Result<Response, anyhow::Error>,
-                    Duration,
-                    u64,
-                    Option<OsString>,
-                ),
-            > + Unpin,
+        S: Stream<Item = RequestOutcome> + Unpin,
         F: Fn(&LoadTestResult),
     {
         let mut result = LoadTestResult::new();
@@ -620,45 +2240,139 @@ impl LoadTestRunner {
             fs::create_dir_all(output_dir).await?;
         }
         let test_time = Instant::now();
-        while let Some((res, duration, iteration, base_file_name)) = stream.next().await {
+        let endpoint = Self::endpoint(&self.url);
+        while let Some(outcome) = stream.next().await {
+            let RequestOutcome {
+                response: res,
+                duration,
+                base_file_name,
+                context,
+            } = outcome;
             result.completed += 1;
+            let (bytes_sent, uncompressed_bytes_sent) = context.body_size;
+            result.bytes_sent += bytes_sent;
+            result.uncompressed_bytes_sent += uncompressed_bytes_sent;
+            let name = context.name.clone().unwrap_or_else(|| endpoint.clone());
+            let group = result
+                .groups
+                .entry(name.clone())
+                .or_insert_with(LoadTestResult::new);
+            group.completed += 1;
+            group.bytes_sent += bytes_sent;
+            group.uncompressed_bytes_sent += uncompressed_bytes_sent;
+            if res.is_ok() {
+                group.success += 1;
+            } else {
+                group.failures += 1;
+            }
+            if self.includes_in_stats(res.is_ok()) {
+                Self::update_stats(group, duration, test_time);
+                // Only the requests of the group included in the statistics count towards its
+                // average.
+                group.avg = group.total_duration / group.durations.len() as u32;
+            }
+            if !self.recorders.is_empty() {
+                let sample = Sample {
+                    name,
+                    endpoint: context.endpoint.clone().unwrap_or_else(|| endpoint.clone()),
+                    status: Self::get_status(&res),
+                    success: res.is_ok(),
+                    duration,
+                    timestamp: SystemTime::now(),
+                    trace: context.trace.clone(),
+                };
+                for recorder in &self.recorders {
+                    recorder.record(&sample);
+                }
+            }
             match res {
                 Ok(response) => {
                     result.success += 1;
-                    if self.stats == Stats::All || self.stats == Stats::Success {
+                    if self.includes_in_stats(true) {
                         Self::update_stats(&mut result, duration, test_time)
                     }
                     if let Some(output_dir) = output_dir {
                         let output_file = Self::get_output_file(
                             self.requests,
                             output_dir,
-                            iteration + 1,
+                            context.iteration + 1,
                             &base_file_name,
                             true,
                         );
-                        Self::write_success_output_file(&output_file, response, duration).await?;
+                        Self::write_success_output_file(&output_file, response, duration, &context)
+                            .await?;
                     }
                 }
                 Err(error) => {
                     result.failures += 1;
-                    if self.stats == Stats::All || self.stats == Stats::Error {
+                    if self.includes_in_stats(false) {
                         Self::update_stats(&mut result, duration, test_time)
                     }
                     if let Some(output_dir) = output_dir {
                         let output_file = Self::get_output_file(
                             self.requests,
                             output_dir,
-                            iteration + 1,
+                            context.iteration + 1,
                             &base_file_name,
                             false,
                         );
-                        Self::write_failure_output_file(&output_file, &error).await?;
+                        Self::write_failure_output_file(&output_file, &error, &context).await?;
                     }
                 }
             }
             in_progress(&result);
         }
 
+        Self::update_quantiles(&mut result);
+        result.avg = if result.completed > 0 {
+            result.total_duration / result.completed
+        } else {
+            Duration::new(0, 0)
+        };
+        result.rps = result.success as f64 / test_time.elapsed().as_secs_f64();
+        for group in result.groups.values_mut() {
+            Self::update_quantiles(group);
+            if !group.durations.is_empty() {
+                group.avg = group.total_duration / group.durations.len() as u32;
+            }
+            group.rps = group.success as f64 / test_time.elapsed().as_secs_f64();
+        }
+        for recorder in &self.recorders {
+            recorder.finish().await?;
+            result.dropped_samples += recorder.dropped();
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the path of the URL without rendering it, so that templated URLs share the same
+    /// endpoint.
+    pub(crate) fn endpoint(url: &str) -> String {
+        let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let path = url.find('/').map_or("/", |i| &url[i..]);
+        path.split(['?', '#']).next().unwrap_or(path).to_owned()
+    }
+
+    fn get_status(response: &Result<Response>) -> Option<u16> {
+        match response {
+            Ok(response) => Some(response.status().as_u16()),
+            Err(error) => error
+                .downcast_ref::<reqwest::Error>()
+                .and_then(|e| e.status())
+                .map(|status| status.as_u16()),
+        }
+    }
+
+    /// Returns `true` if a request with the specified outcome is included in the statistics.
+    fn includes_in_stats(&self, success: bool) -> bool {
+        match self.stats {
+            Stats::All => true,
+            Stats::Success => success,
+            Stats::Error => !success,
+        }
+    }
+
+    fn update_quantiles(result: &mut LoadTestResult) {
         if let [p50, p90, p95] =
             Self::get_quantiles(&mut result.durations, &[0.5, 0.90, 0.95]).as_slice()
         {
@@ -666,14 +2380,6 @@ impl LoadTestRunner {
             result.p90 = *p90;
             result.p95 = *p95;
         }
-        result.avg = if self.requests > 0 {
-            result.total_duration / self.requests
-        } else {
-            Duration::new(0, 0)
-        };
-        result.rps = result.success as f64 / test_time.elapsed().as_secs_f64();
-
-        Ok(result)
     }
 
     fn update_stats(result: &mut LoadTestResult, duration: Duration, test_time: Instant) {
@@ -689,8 +2395,14 @@ impl LoadTestRunner {
         result.durations.push(duration);
     }
 
-    async fn get(&self, headers: HeaderMap, error_for_status: bool) -> Result<Response> {
-        let response = self.client.get(&self.url).headers(headers).send().await?;
+    async fn get(
+        &self,
+        client: &Client,
+        url: &str,
+        headers: HeaderMap,
+        error_for_status: bool,
+    ) -> Result<Response> {
+        let response = client.get(url).headers(headers).send().await?;
         Ok(if error_for_status {
             response.error_for_status()?
         } else {
@@ -700,17 +2412,13 @@ impl LoadTestRunner {
 
     async fn post(
         &self,
+        client: &Client,
+        url: &str,
         headers: HeaderMap,
-        body: Bytes,
+        body: reqwest::Body,
         error_for_status: bool,
     ) -> Result<Response> {
-        let response = self
-            .client
-            .post(&self.url)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await?;
+        let response = client.post(url).headers(headers).body(body).send().await?;
         Ok(if error_for_status {
             response.error_for_status()?
         } else {
@@ -720,17 +2428,13 @@ impl LoadTestRunner {
 
     async fn put(
         &self,
+        client: &Client,
+        url: &str,
         headers: HeaderMap,
-        body: Bytes,
+        body: reqwest::Body,
         error_for_status: bool,
     ) -> Result<Response> {
-        let response = self
-            .client
-            .put(&self.url)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await?;
+        let response = client.put(url).headers(headers).body(body).send().await?;
         Ok(if error_for_status {
             response.error_for_status()?
         } else {
@@ -740,13 +2444,14 @@ impl LoadTestRunner {
 
     async fn delete(
         &self,
+        client: &Client,
+        url: &str,
         headers: HeaderMap,
-        body: Bytes,
+        body: reqwest::Body,
         error_for_status: bool,
     ) -> Result<Response> {
-        let response = self
-            .client
-            .delete(&self.url)
+        let response = client
+            .delete(url)
             .headers(headers)
             .body(body)
             .send()
@@ -760,17 +2465,13 @@ impl LoadTestRunner {
 
     async fn patch(
         &self,
+        client: &Client,
+        url: &str,
         headers: HeaderMap,
-        body: Bytes,
+        body: reqwest::Body,
         error_for_status: bool,
     ) -> Result<Response> {
-        let response = self
-            .client
-            .patch(&self.url)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await?;
+        let response = client.patch(url).headers(headers).body(body).send().await?;
         Ok(if error_for_status {
             response.error_for_status()?
         } else {
@@ -778,8 +2479,14 @@ impl LoadTestRunner {
         })
     }
 
-    async fn head(&self, headers: HeaderMap, error_for_status: bool) -> Result<Response> {
-        let response = self.client.head(&self.url).headers(headers).send().await?;
+    async fn head(
+        &self,
+        client: &Client,
+        url: &str,
+        headers: HeaderMap,
+        error_for_status: bool,
+    ) -> Result<Response> {
+        let response = client.head(url).headers(headers).send().await?;
         Ok(if error_for_status {
             response.error_for_status()?
         } else {
@@ -787,7 +2494,7 @@ impl LoadTestRunner {
         })
     }
 
-    fn get_quantiles(durations: &mut [Duration], quantiles: &[f64]) -> Vec<Duration> {
+    pub(crate) fn get_quantiles(durations: &mut [Duration], quantiles: &[f64]) -> Vec<Duration> {
         durations.sort();
         quantiles
             .iter()
@@ -827,6 +2534,7 @@ impl LoadTestRunner {
         output_file: &Path,
         response: Response,
         duration: Duration,
+        context: &RequestContext,
     ) -> Result<()> {
         let version: String = format!("{:?}", response.version());
         let status_code = response.status().as_u16();
@@ -840,20 +2548,35 @@ impl LoadTestRunner {
             Ok(bytes) => bytes.to_string(),
             Err(_) => BASE64_STANDARD.encode(&body_bytes),
         };
-        let output = json!({
+        let mut output = json!({
             "version": version,
             "status": status_code,
             "headers": headers,
             "body": body_string,
             "duration": duration,
         });
+        Self::add_context(&mut output, context);
         Ok(fs::write(output_file, serde_json::to_string_pretty(&output)?).await?)
     }
 
-    async fn write_failure_output_file(output_file: &Path, error: &anyhow::Error) -> Result<()> {
-        let output = json!({
+    fn add_context(output: &mut serde_json::Value, context: &RequestContext) {
+        if let Some(trace) = &context.trace {
+            output["trace_id"] = json!(trace.trace_id);
+        }
+        if let Some(request_id) = &context.request_id {
+            output["request_id"] = json!(request_id);
+        }
+    }
+
+    async fn write_failure_output_file(
+        output_file: &Path,
+        error: &anyhow::Error,
+        context: &RequestContext,
+    ) -> Result<()> {
+        let mut output = json!({
             "error": error.to_string(),
         });
+        Self::add_context(&mut output, context);
         Ok(fs::write(output_file, serde_json::to_string_pretty(&output)?).await?)
     }
 }
@@ -1113,4 +2836,84 @@ mod tests {
         );
         assert_eq!(output_file.as_os_str(), "/tmp/failure-003-request.json");
     }
+
+    #[tokio::test]
+    async fn prepare_template_succeeds() {
+        let runner = LoadTestRunner::new(
+            "http://localhost:8080/api/",
+            2,
+            2,
+            Stats::Success,
+            &None,
+            &None,
+            &None,
+            &None,
+        )
+        .await
+        .unwrap();
+        let template: RequestTemplate = serde_json::from_str(
+            r#"{"method": "delete", "path": "/cart/1", "query": {"b": "x y", "a": "1"}}"#,
+        )
+        .unwrap();
+
+        let request = runner.prepare_template(&template, None).await.unwrap();
+        let (url, _, _) = request.render(&RenderContext::default()).unwrap();
+
+        assert_eq!(request.method, Some(HttpMethod::Delete));
+        assert_eq!(request.endpoint, "/api/cart/1");
+        assert_eq!(url, "http://localhost:8080/api/cart/1?a=1&b=x+y");
+    }
+
+    #[tokio::test]
+    async fn prepare_keeps_header_values_without_templating() {
+        let runner = LoadTestRunner::new(
+            "http://localhost:8080",
+            2,
+            2,
+            Stats::Success,
+            &None,
+            &None,
+            &None,
+            &None,
+        )
+        .await
+        .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-name", HeaderValue::from_bytes(b"caf\xe9").unwrap());
+
+        let request = runner
+            .prepare(&headers, BodyTemplate::Static(Bytes::new()))
+            .unwrap();
+        let (_, headers, _) = request.render(&RenderContext::default()).unwrap();
+
+        assert_eq!(headers["x-name"].as_bytes(), b"caf\xe9");
+    }
+
+    #[tokio::test]
+    async fn prepare_template_invalid_method_fails() {
+        let runner = LoadTestRunner::new(
+            "http://localhost:8080",
+            2,
+            2,
+            Stats::Success,
+            &None,
+            &None,
+            &None,
+            &None,
+        )
+        .await
+        .unwrap();
+        let template: RequestTemplate = serde_json::from_str(r#"{"method": "fetch"}"#).unwrap();
+
+        let err = runner.prepare_template(&template, None).await.unwrap_err();
+
+        assert_eq!(err.to_string(), "'fetch' is not a valid HTTP method");
+    }
+
+    #[test]
+    fn request_template_unknown_field_fails() {
+        let err = serde_json::from_str::<RequestTemplate>(r#"{"header": {"A": "1"}}"#).unwrap_err();
+
+        assert!(err.to_string().starts_with("unknown field `header`"));
+    }
 }
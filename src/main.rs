@@ -1,27 +1,59 @@
 use anyhow::{Context, Result, bail};
 use bytes::Bytes;
-use clap::Parser;
+use clap::{ArgGroup, Parser, Subcommand};
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
-use load_rs::{Body, HttpMethod, LoadTestRunner, Order, Stats};
+use load_rs::access_log::AccessLog;
+use load_rs::compression::BodyCompression;
+use load_rs::curl::CurlCommand;
+use load_rs::data_source::PickMode;
+use load_rs::feeder::Feeder;
+use load_rs::form::FormField;
+use load_rs::har::{Har, HarFilter};
+use load_rs::influx::{InfluxDestination, InfluxExporter};
+use load_rs::manifest::{DEFAULT_BLOCK_SIZE, validate_manifest};
+use load_rs::openapi::OpenApi;
+use load_rs::postman::PostmanCollection;
+use load_rs::prometheus::PrometheusExporter;
+use load_rs::scenario::Scenario;
+use load_rs::statsd::{StatsdClient, parse_tag};
+use load_rs::think_time::{ThinkTime, parse_duration};
+use load_rs::trace::{OtlpExporter, parse_sample_ratio};
+use load_rs::upload::{BodyStreaming, parse_chunk_size, parse_size};
+use load_rs::{
+    Body, HttpMethod, LoadTestResult, LoadTestRunner, Order, Replay, RequestIdFormat, Stats,
+};
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
 
 /// load-rs: A simple load testing tool written in Rust.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(group(ArgGroup::new("ordered").args(["data_dir", "url_file", "manifest_file", "har", "access_log", "curl", "openapi"])))]
+#[command(group(ArgGroup::new("recorded").args(["har", "access_log"])))]
+#[command(group(ArgGroup::new("files").args(["data_file", "data_dir"])))]
 struct Args {
-    /// Target URL to send requests to.
-    url: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Target URL to send requests to. Defaults to the URL of the first --curl command.
+    #[arg(required_unless_present = "curl")]
+    url: Option<String>,
 
     /// Total number of requests to send.
-    #[arg(short = 'n', long)]
-    requests: u32,
+    #[arg(short = 'n', long, required = true)]
+    requests: Option<u32>,
 
     /// Number of concurrent requests to run at a time.
-    #[arg(short = 'c', long)]
-    concurrency: u32,
+    #[arg(short = 'c', long, required = true)]
+    concurrency: Option<u32>,
 
     /// HTTP method to use for the requests.
     #[arg(short = 'X', long, value_parser = parse_http_method, default_value = "get")]
@@ -35,18 +67,129 @@ struct Args {
     #[arg(short = 'd', long, group = "request_body")]
     data: Option<String>,
 
-    /// File to read the request body from.
+    /// File to read the request body from, or "-" for stdin.
     #[arg(short = 'D', long = "data-file", group = "request_body")]
     data_file: Option<PathBuf>,
 
+    /// Multipart form field in "name=value", "name=@file" or "name=<file" format. Can be repeated.
+    #[arg(short = 'F', long, group = "request_body", value_parser = FormField::from_str)]
+    form: Vec<FormField>,
+
+    /// URL-encoded form field in "name=value" format. Can be repeated.
+    #[arg(long = "form-urlencoded", group = "request_body", value_parser = parse_form_urlencoded)]
+    form_urlencoded: Vec<(String, String)>,
+
+    /// Compresses the request bodies with gzip, br or zstd and sets their Content-Encoding header.
+    #[arg(long = "compress-body", value_parser = BodyCompression::from_str)]
+    compress_body: Option<BodyCompression>,
+
     /// Directory of files to use as request bodies.
     #[arg(short = 'i', long = "data-dir", group = "request_body")]
     data_dir: Option<PathBuf>,
 
-    /// Request manifest file (JSON Lines format).
+    /// File of URLs, or paths relative to the target URL, one per line, or "-" for stdin.
+    #[arg(
+        long = "url-file",
+        conflicts_with_all = ["data_dir", "manifest_file", "scenario", "har", "curl", "access_log", "openapi"]
+    )]
+    url_file: Option<PathBuf>,
+
+    /// Streams --data-file or the files of --data-dir from disk as chunked bodies.
+    #[arg(
+        long = "stream-body",
+        requires = "files",
+        conflicts_with = "compress_body"
+    )]
+    stream_body: bool,
+
+    /// Size of the chunks of a --stream-body body, e.g. 64k or 1m, up to 16m.
+    #[arg(long = "chunk-size", value_parser = parse_chunk_size, default_value = "64k", requires = "stream_body")]
+    chunk_size: u64,
+
+    /// Maximum upload bandwidth per request in bytes per second, e.g. 512k.
+    #[arg(long = "upload-rate", value_parser = parse_size, requires = "stream_body")]
+    upload_rate: Option<u64>,
+
+    /// Request manifest file (JSON Lines format), or "-" for stdin, which is always streamed.
     #[arg(short = 'm', long = "manifest-file", group = "request_body")]
     manifest_file: Option<PathBuf>,
 
+    /// Validates --manifest-file and prints its request mix without sending any requests.
+    #[arg(long = "dry-run", requires = "manifest_file", conflicts_with = "debug")]
+    dry_run: bool,
+
+    /// Reads --manifest-file lazily in blocks instead of all at once, e.g. for huge manifests.
+    #[arg(long, requires = "manifest_file")]
+    stream: bool,
+
+    /// Starts over at the end of a --stream manifest instead of failing the remaining requests.
+    #[arg(long = "loop", requires = "stream")]
+    looping: bool,
+
+    /// Number of --stream manifest lines in memory, which are shuffled or sampled by --order.
+    #[arg(long = "block-size", default_value_t = DEFAULT_BLOCK_SIZE, requires = "stream")]
+    block_size: usize,
+
+    /// Scenario file (JSON Lines format) whose steps are run in order for every iteration.
+    #[arg(short = 'S', long, group = "request_body", conflicts_with = "debug")]
+    scenario: Option<PathBuf>,
+
+    /// HAR file whose entries are replayed.
+    #[arg(long, group = "request_body", conflicts_with = "debug")]
+    har: Option<PathBuf>,
+
+    /// Only replays HAR entries of a domain, including its subdomains. Can be repeated.
+    #[arg(long = "har-domain", requires = "har")]
+    har_domain: Vec<String>,
+
+    /// Only replays HAR entries whose response content type starts with a prefix. Can be repeated.
+    #[arg(long = "har-content-type", requires = "har")]
+    har_content_type: Vec<String>,
+
+    /// Curl command, or a file of curl commands, whose requests are sent.
+    #[arg(long, group = "request_body", conflicts_with = "debug")]
+    curl: Option<String>,
+
+    /// Access log (nginx/Apache combined log or JSON Lines format) whose requests are replayed.
+    #[arg(long = "access-log", group = "request_body", conflicts_with = "debug")]
+    access_log: Option<PathBuf>,
+
+    /// OpenAPI 3 document (JSON or YAML) whose operations are sent with generated examples.
+    #[arg(long, group = "request_body", conflicts_with = "debug")]
+    openapi: Option<PathBuf>,
+
+    /// Weight of an --openapi operation by operationId, e.g. listPets=5. Can be repeated.
+    #[arg(long = "operation-weight", value_parser = parse_operation_weight, requires = "openapi")]
+    operation_weight: Vec<(String, u32)>,
+
+    /// Fails the responses that do not match the response schemas of the --openapi document.
+    #[arg(long = "validate-responses", requires = "openapi")]
+    validate_responses: bool,
+
+    /// Replays --har or --access-log requests at their original relative times instead of in --order.
+    #[arg(long = "preserve-timing", requires = "recorded")]
+    preserve_timing: bool,
+
+    /// Speed-up factor of the original timing, e.g. 2x. Implies --preserve-timing.
+    #[arg(long = "replay-speed", value_parser = parse_replay_speed, requires = "recorded")]
+    replay_speed: Option<f64>,
+
+    /// Runs --concurrency virtual users, each with its own cookie jar, variables and connections.
+    #[arg(short = 'U', long = "virtual-users")]
+    virtual_users: bool,
+
+    /// Shares the connection pool between virtual users.
+    #[arg(long = "shared-connections", requires = "virtual_users")]
+    shared_connections: bool,
+
+    /// Pause after every request, e.g. 2s (fixed), 1s..3s (uniform) or exp:2s (exponential).
+    #[arg(long = "think-time", value_parser = ThinkTime::from_str)]
+    think_time: Option<ThinkTime>,
+
+    /// Minimum interval between the starts of two iterations of the same user, e.g. 2s.
+    #[arg(long, value_parser = parse_duration)]
+    pacing: Option<Duration>,
+
     /// Custom CA certificate file (PEM format).
     #[arg(short = 'C', long = "cacert")]
     ca_cert: Option<PathBuf>,
@@ -63,8 +206,20 @@ struct Args {
     #[arg(short = 'I', long)]
     insecure: Option<bool>,
 
-    /// Order to process files from --data-dir or --manifest-file.
-    #[arg(short = 'O', long, value_parser = parse_order, default_value = "sequential", requires = "data_dir")]
+    /// Renders {{...}} expressions in the URL, headers and bodies for every request.
+    #[arg(short = 'T', long)]
+    template: bool,
+
+    /// CSV or JSON Lines file whose rows supply {{column}} variables. Implies --template.
+    #[arg(long)]
+    feeder: Option<PathBuf>,
+
+    /// How to pick feeder rows.
+    #[arg(long = "feeder-mode", value_parser = parse_pick_mode, default_value = "circular", requires = "feeder")]
+    feeder_mode: PickMode,
+
+    /// Order to process files from --data-dir, --url-file, --manifest-file, --har, --access-log, --curl or --openapi.
+    #[arg(short = 'O', long, value_parser = parse_order, default_value = "sequential", requires = "ordered")]
     order: Order,
 
     /// Directory to save responses to.
@@ -78,28 +233,172 @@ struct Args {
     /// Specifies which requests to include in the statistics.
     #[arg(short = 's', long, value_parser = parse_stats, default_value = "success")]
     stats: Stats,
+
+    /// Address to serve Prometheus metrics on during the run.
+    #[arg(long = "metrics-listen")]
+    metrics_listen: Option<SocketAddr>,
+
+    /// StatsD server to send metrics to in "host:port" format.
+    #[arg(long)]
+    statsd: Option<String>,
+
+    /// Prefix of the metric names sent to StatsD.
+    #[arg(long = "statsd-prefix", default_value = "load_rs", requires = "statsd")]
+    statsd_prefix: String,
+
+    /// Tag(s) in "key:value" format to add to the StatsD metrics. Can be repeated.
+    #[arg(long = "statsd-tag", action = clap::ArgAction::Append, value_parser = parse_tag, requires = "statsd")]
+    statsd_tag: Vec<String>,
+
+    /// ID of the run used in exported metrics and request IDs. Defaults to a random UUID.
+    #[arg(long = "run-id")]
+    run_id: Option<String>,
+
+    /// File or HTTP write endpoint to write InfluxDB line protocol metrics to.
+    #[arg(long)]
+    influx: Option<String>,
+
+    /// Interval in seconds to aggregate the InfluxDB metrics over.
+    #[arg(long = "influx-interval", default_value_t = 1, requires = "influx")]
+    influx_interval: u64,
+
+    /// Tag(s) in "key=value" format to add to the InfluxDB metrics. Can be repeated.
+    #[arg(long = "influx-tag", action = clap::ArgAction::Append, requires = "influx")]
+    influx_tag: Vec<String>,
+
+    /// Injects a unique W3C "traceparent" header into every request.
+    #[arg(long = "trace-context")]
+    trace_context: bool,
+
+    /// Ratio of requests between 0.0 and 1.0 to sample for tracing.
+    #[arg(long = "trace-sample-ratio", value_parser = parse_sample_ratio, default_value = "1.0")]
+    trace_sample_ratio: f64,
+
+    /// OTLP/HTTP collector to export a client span per sampled request to. Implies --trace-context.
+    #[arg(long = "otlp-endpoint")]
+    otlp_endpoint: Option<String>,
+
+    /// Service name of the exported spans.
+    #[arg(
+        long = "otlp-service-name",
+        default_value = "load-rs",
+        requires = "otlp_endpoint"
+    )]
+    otlp_service_name: String,
+
+    /// Header to inject a unique request ID into, e.g. "X-Request-Id".
+    #[arg(long = "request-id-header")]
+    request_id_header: Option<String>,
+
+    /// Format of the request ID: a random UUID, or the run ID followed by the iteration number.
+    #[arg(long = "request-id-format", value_parser = parse_request_id_format, default_value = "uuid", requires = "request_id_header")]
+    request_id_format: RequestIdFormat,
+}
+
+#[derive(Subcommand, Debug)]
+#[allow(clippy::enum_variant_names)]
+enum Command {
+    /// Converts curl commands into request manifest lines.
+    FromCurl {
+        /// Curl command, or a file of curl commands.
+        curl: String,
+    },
+
+    /// Converts the operations of an OpenAPI 3 document into request manifest lines.
+    FromOpenapi {
+        /// OpenAPI 3 document (JSON or YAML).
+        openapi: PathBuf,
+
+        /// Weight of an operation by operationId, e.g. listPets=5. Can be repeated.
+        #[arg(long = "operation-weight", value_parser = parse_operation_weight)]
+        operation_weight: Vec<(String, u32)>,
+    },
+
+    /// Converts a Postman collection (v2.0 or v2.1) into request manifest lines.
+    FromPostman {
+        /// Postman collection file.
+        collection: PathBuf,
+
+        /// Postman environment file whose variables override the collection variables.
+        #[arg(short = 'e', long)]
+        environment: Option<PathBuf>,
+    },
+
+    /// Validates a request manifest file without sending any requests.
+    Validate {
+        /// Request manifest file (JSON Lines format), or "-" for stdin.
+        #[arg(short = 'm', long = "manifest-file")]
+        manifest_file: PathBuf,
+
+        /// HTTP method of the requests without a method.
+        #[arg(short = 'X', long, value_parser = parse_http_method, default_value = "get")]
+        method: HttpMethod,
+
+        /// Validates the {{...}} expressions in the URL, headers and bodies.
+        #[arg(short = 'T', long)]
+        template: bool,
+
+        /// CSV or JSON Lines file whose columns are known {{column}} variables. Implies --template.
+        #[arg(long)]
+        feeder: Option<PathBuf>,
+    },
 }
 
 fn parse_http_method(s: &str) -> Result<HttpMethod> {
-    match s.to_ascii_lowercase().as_str() {
-        "get" => Ok(HttpMethod::Get),
-        "post" => Ok(HttpMethod::Post),
-        "put" => Ok(HttpMethod::Put),
-        "delete" => Ok(HttpMethod::Delete),
-        "patch" => Ok(HttpMethod::Patch),
-        "head" => Ok(HttpMethod::Head),
-        _ => bail!("'{s}' is not a valid HTTP method"),
-    }
+    HttpMethod::from_str(s)
 }
 
 fn parse_order(s: &str) -> Result<Order> {
     match s.to_ascii_lowercase().as_str() {
         "sequential" => Ok(Order::Sequential),
         "random" => Ok(Order::Random),
+        "weighted" => Ok(Order::Weighted),
         _ => bail!("'{s}' is not a valid read order"),
     }
 }
 
+fn parse_pick_mode(s: &str) -> Result<PickMode> {
+    match s.to_ascii_lowercase().as_str() {
+        "sequential" => Ok(PickMode::Sequential),
+        "random" => Ok(PickMode::Random),
+        "unique" => Ok(PickMode::Unique),
+        "circular" => Ok(PickMode::Circular),
+        _ => bail!("'{s}' is not a valid feeder mode"),
+    }
+}
+
+fn parse_replay_speed(s: &str) -> Result<f64> {
+    match s.strip_suffix('x').unwrap_or(s).parse::<f64>() {
+        Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(speed),
+        _ => bail!("'{s}' is not a valid replay speed"),
+    }
+}
+
+fn parse_operation_weight(s: &str) -> Result<(String, u32)> {
+    match s.rsplit_once('=') {
+        Some((name, weight)) if !name.is_empty() => match weight.parse() {
+            Ok(weight) => Ok((name.to_owned(), weight)),
+            Err(_) => bail!("'{weight}' is not a valid weight"),
+        },
+        _ => bail!("'{s}' is not a valid operation weight, expected NAME=WEIGHT"),
+    }
+}
+
+fn parse_form_urlencoded(s: &str) -> Result<(String, String)> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_owned(), value.to_owned())),
+        _ => bail!("'{s}' is not a valid form field, expected NAME=VALUE"),
+    }
+}
+
+fn parse_request_id_format(s: &str) -> Result<RequestIdFormat> {
+    match s.to_ascii_lowercase().as_str() {
+        "uuid" => Ok(RequestIdFormat::Uuid),
+        "iteration" => Ok(RequestIdFormat::Iteration),
+        _ => bail!("'{s}' is not a valid request ID format"),
+    }
+}
+
 fn parse_stats(s: &str) -> Result<Stats> {
     match s.to_ascii_lowercase().as_str() {
         "success" => Ok(Stats::Success),
@@ -109,6 +408,30 @@ fn parse_stats(s: &str) -> Result<Stats> {
     }
 }
 
+async fn read_curl(curl: &str) -> Result<Vec<CurlCommand>> {
+    if Path::new(curl).is_file() {
+        return CurlCommand::from_file(Path::new(curl)).await;
+    }
+    let commands = CurlCommand::parse_all(curl).await?;
+    if commands.is_empty() {
+        bail!("'{curl}' is not a curl command");
+    }
+    Ok(commands)
+}
+
+async fn read_openapi(openapi: &Path, weights: &[(String, u32)]) -> Result<OpenApi> {
+    let weights = weights.iter().cloned().collect();
+    OpenApi::from_file(openapi).await?.with_weights(&weights)
+}
+
+fn to_replay(args: &Args) -> Replay {
+    if args.preserve_timing || args.replay_speed.is_some() {
+        Replay::Timing(args.replay_speed.unwrap_or(1.0))
+    } else {
+        Replay::Order(args.order)
+    }
+}
+
 fn to_header_map(headers: &[String]) -> Result<HeaderMap> {
     headers
         .iter()
@@ -128,13 +451,71 @@ fn to_body(args: &Args) -> Body {
         Body::Data(data.to_owned().into())
     } else if let Some(data_file) = &args.data_file {
         Body::DataFile(data_file.to_owned())
+    } else if !args.form.is_empty() {
+        Body::Multipart(args.form.clone())
+    } else if !args.form_urlencoded.is_empty() {
+        Body::UrlEncoded(args.form_urlencoded.clone())
     } else {
         Body::Data(Bytes::new())
     }
 }
 
-fn create_progress_bar(len: u32) -> Result<ProgressBar> {
-    let pb = ProgressBar::new(len as u64);
+async fn validate(
+    manifest_file: &Path,
+    method: HttpMethod,
+    template: bool,
+    feeder: Option<&Path>,
+) -> Result<()> {
+    let variables = match feeder {
+        Some(feeder) => Some(
+            Feeder::from_file(feeder, PickMode::Sequential)
+                .await?
+                .columns()
+                .clone(),
+        ),
+        None if template => Some(BTreeSet::new()),
+        None => None,
+    };
+    let report = validate_manifest(manifest_file, method, variables.as_ref()).await?;
+    for (line, error) in &report.errors {
+        eprintln!("{} Line {line}: {error}", style("Error:").red());
+    }
+    if report.invalid > report.errors.len() {
+        eprintln!("...");
+    }
+    let valid = report.lines - report.invalid;
+    let weight: u64 = report.mix.values().map(|mix| mix.weight).sum();
+    println!(
+        "🔍 {} valid and {} invalid lines in {}",
+        style(valid).green(),
+        style(report.invalid).red(),
+        manifest_file.display()
+    );
+    for (name, mix) in &report.mix {
+        println!(
+            "{}: Requests: {} | Weight: {} ({:.1}%)",
+            style(name).bold(),
+            mix.count,
+            mix.weight,
+            if weight == 0 {
+                0.0
+            } else {
+                mix.weight as f64 * 100.0 / weight as f64
+            }
+        );
+    }
+    if report.invalid > 0 {
+        bail!(
+            "Manifest file '{}' has {} invalid lines",
+            manifest_file.display(),
+            report.invalid
+        );
+    }
+    Ok(())
+}
+
+fn create_progress_bar(len: u64) -> Result<ProgressBar> {
+    let pb = ProgressBar::new(len);
     pb.set_style(
         ProgressStyle::default_bar()
             .template("[{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {msg}")?
@@ -144,13 +525,33 @@ fn create_progress_bar(len: u32) -> Result<ProgressBar> {
     Ok(pb)
 }
 
-async fn run(runner: &LoadTestRunner, args: &Args) -> Result<()> {
+async fn run(runner: &LoadTestRunner, args: &Args, curl: Option<&[CurlCommand]>) -> Result<()> {
     println!(
         "🚀🚀🚀 Sending {} requests to {} with {} concurrency 🚀🚀🚀",
-        args.requests, args.url, args.concurrency
+        runner.requests, runner.url, runner.concurrency
     );
-    let pb = create_progress_bar(args.requests)?;
-    let result = if let Some(data_dir) = &args.data_dir {
+    let scenario = match &args.scenario {
+        Some(scenario) => Some(Scenario::from_file(scenario).await?),
+        None => None,
+    };
+    let steps = scenario
+        .as_ref()
+        .map_or(1, |scenario| scenario.len() as u64);
+    let pb = create_progress_bar((runner.requests as u64).saturating_mul(steps))?;
+    let result = if let Some(scenario) = &scenario {
+        runner
+            .run_scenario(args.method, scenario, &args.output_dir, |result| {
+                pb.set_message(format!(
+                    "\nSuccess: {} | Failures: {} | RPS: {:.2?} | Avg: {:.2?}",
+                    style(result.success).green(),
+                    style(result.failures).red(),
+                    result.rps,
+                    result.avg
+                ));
+                pb.set_position(result.completed as u64);
+            })
+            .await?
+    } else if let Some(data_dir) = &args.data_dir {
         runner
             .run_from_dir(
                 args.method,
@@ -170,6 +571,111 @@ async fn run(runner: &LoadTestRunner, args: &Args) -> Result<()> {
                 },
             )
             .await?
+    } else if let Some(url_file) = &args.url_file {
+        runner
+            .run_from_url_file(
+                args.method,
+                Some(to_header_map(&args.header)?),
+                Some(to_body(args)),
+                url_file,
+                args.order,
+                &args.output_dir,
+                |result| {
+                    pb.set_message(format!(
+                        "\nSuccess: {} | Failures: {} | RPS: {:.2?} | Avg: {:.2?}",
+                        style(result.success).green(),
+                        style(result.failures).red(),
+                        result.rps,
+                        result.avg
+                    ));
+                    pb.inc(1);
+                },
+            )
+            .await?
+    } else if let Some(har) = &args.har {
+        let filter = HarFilter {
+            domains: args.har_domain.clone(),
+            content_types: args.har_content_type.clone(),
+        };
+        let har = Har::from_file(har, &filter).await?;
+        runner
+            .run_from_har(
+                args.method,
+                &har,
+                to_replay(args),
+                &args.output_dir,
+                |result| {
+                    pb.set_message(format!(
+                        "\nSuccess: {} | Failures: {} | RPS: {:.2?} | Avg: {:.2?}",
+                        style(result.success).green(),
+                        style(result.failures).red(),
+                        result.rps,
+                        result.avg
+                    ));
+                    pb.inc(1);
+                },
+            )
+            .await?
+    } else if let Some(commands) = curl {
+        runner
+            .run_from_curl(
+                args.method,
+                commands,
+                args.order,
+                &args.output_dir,
+                |result| {
+                    pb.set_message(format!(
+                        "\nSuccess: {} | Failures: {} | RPS: {:.2?} | Avg: {:.2?}",
+                        style(result.success).green(),
+                        style(result.failures).red(),
+                        result.rps,
+                        result.avg
+                    ));
+                    pb.inc(1);
+                },
+            )
+            .await?
+    } else if let Some(openapi) = &args.openapi {
+        let openapi = read_openapi(openapi, &args.operation_weight).await?;
+        runner
+            .run_from_openapi(
+                args.method,
+                &openapi,
+                args.order,
+                args.validate_responses,
+                &args.output_dir,
+                |result| {
+                    pb.set_message(format!(
+                        "\nSuccess: {} | Failures: {} | RPS: {:.2?} | Avg: {:.2?}",
+                        style(result.success).green(),
+                        style(result.failures).red(),
+                        result.rps,
+                        result.avg
+                    ));
+                    pb.inc(1);
+                },
+            )
+            .await?
+    } else if let Some(access_log) = &args.access_log {
+        let access_log = AccessLog::from_file(access_log).await?;
+        runner
+            .run_from_access_log(
+                args.method,
+                &access_log,
+                to_replay(args),
+                &args.output_dir,
+                |result| {
+                    pb.set_message(format!(
+                        "\nSuccess: {} | Failures: {} | RPS: {:.2?} | Avg: {:.2?}",
+                        style(result.success).green(),
+                        style(result.failures).red(),
+                        result.rps,
+                        result.avg
+                    ));
+                    pb.inc(1);
+                },
+            )
+            .await?
     } else if let Some(manifest_file) = &args.manifest_file {
         runner
             .run_from_manifest(
@@ -215,9 +721,43 @@ async fn run(runner: &LoadTestRunner, args: &Args) -> Result<()> {
         "✅ Done!\nSuccess: {} | Failures: {} | RPS: {:.2?} | Avg: {:.2?} | Min: {:.2?} | Max: {:.2?} | P50: {:.2?} | P90: {:.2?} | P95: {:.2?}",
         style(result.success).green(), style(result.failures).red(), result.rps, result.avg, result.min, result.max, result.p50, result.p90, result.p95
     ));
+    if args.compress_body.is_some() {
+        println!(
+            "Bytes sent: {} | Uncompressed: {} | Ratio: {:.2}",
+            result.bytes_sent,
+            result.uncompressed_bytes_sent,
+            result.bytes_sent as f64 / result.uncompressed_bytes_sent.max(1) as f64
+        );
+    }
+    if result.dropped_samples > 0 {
+        println!(
+            "⚠️ {} samples could not be exported",
+            style(result.dropped_samples).yellow()
+        );
+    }
+    if result.groups.len() > 1 {
+        print_groups(&result);
+    }
     Ok(())
 }
 
+fn print_groups(result: &LoadTestResult) {
+    for (name, group) in &result.groups {
+        println!(
+            "{}: Success: {} | Failures: {} | Avg: {:.2?} | Min: {:.2?} | Max: {:.2?} | P50: {:.2?} | P90: {:.2?} | P95: {:.2?}",
+            style(name).bold(),
+            style(group.success).green(),
+            style(group.failures).red(),
+            group.avg,
+            group.min,
+            group.max,
+            group.p50,
+            group.p90,
+            group.p95
+        );
+    }
+}
+
 async fn debug(runner: &LoadTestRunner, args: &Args) -> Result<()> {
     let response = if let Some(data_dir) = &args.data_dir {
         runner
@@ -228,6 +768,16 @@ async fn debug(runner: &LoadTestRunner, args: &Args) -> Result<()> {
                 args.order,
             )
             .await?
+    } else if let Some(url_file) = &args.url_file {
+        runner
+            .debug_from_url_file(
+                args.method,
+                Some(to_header_map(&args.header)?),
+                Some(to_body(args)),
+                url_file,
+                args.order,
+            )
+            .await?
     } else if let Some(manifest_file) = &args.manifest_file {
         runner
             .debug_from_manifest(args.method, manifest_file, args.order)
@@ -254,21 +804,152 @@ async fn debug(runner: &LoadTestRunner, args: &Args) -> Result<()> {
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
-    let runner = LoadTestRunner::new(
-        &args.url,
-        args.requests,
-        args.concurrency,
+    match &args.command {
+        Some(Command::FromCurl { curl }) => {
+            for command in read_curl(curl).await? {
+                println!("{}", command.to_manifest_line()?);
+            }
+            return Ok(());
+        }
+        Some(Command::FromOpenapi {
+            openapi,
+            operation_weight,
+        }) => {
+            for operation in read_openapi(openapi, operation_weight).await?.operations() {
+                println!("{}", operation.to_manifest_line()?);
+            }
+            return Ok(());
+        }
+        Some(Command::FromPostman {
+            collection,
+            environment,
+        }) => {
+            let collection =
+                PostmanCollection::from_file(collection, environment.as_deref()).await?;
+            for warning in collection.warnings() {
+                eprintln!("{} {warning}", style("Warning:").yellow());
+            }
+            for line in collection.to_manifest_lines()? {
+                println!("{line}");
+            }
+            return Ok(());
+        }
+        Some(Command::Validate {
+            manifest_file,
+            method,
+            template,
+            feeder,
+        }) => {
+            return validate(manifest_file, *method, *template, feeder.as_deref()).await;
+        }
+        None => {}
+    }
+    if args.dry_run
+        && let Some(manifest_file) = &args.manifest_file
+    {
+        return validate(
+            manifest_file,
+            args.method,
+            args.template,
+            args.feeder.as_deref(),
+        )
+        .await;
+    }
+    let run_id = args
+        .run_id
+        .clone()
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let curl = match &args.curl {
+        Some(curl) => Some(read_curl(curl).await?),
+        None => None,
+    };
+    // The URL and the TLS options of the command line take precedence over the first command.
+    let first = curl.as_ref().and_then(|commands| commands.first());
+    let url = args
+        .url
+        .clone()
+        .or_else(|| first.map(|command| command.url().to_owned()))
+        .unwrap_or_default();
+    let cert = args.cert.clone().or_else(|| first?.cert.clone());
+    let key = args.key.clone().or_else(|| first?.key.clone());
+    let mut runner = LoadTestRunner::new(
+        &url,
+        args.requests.unwrap_or_default(),
+        args.concurrency.unwrap_or_default(),
         args.stats,
-        &args.ca_cert,
-        &args.cert,
-        &args.key,
-        &args.insecure,
+        &args.ca_cert.clone().or_else(|| first?.ca_cert.clone()),
+        &cert,
+        &key,
+        &args.insecure.or(first.map(|command| command.insecure)),
     )
     .await?;
+    if let Some(metrics_listen) = args.metrics_listen {
+        let exporter = Arc::new(PrometheusExporter::new());
+        let addr = exporter.clone().serve(metrics_listen).await?;
+        println!("📈 Serving metrics at http://{addr}/metrics");
+        runner = runner.with_recorder(exporter);
+    }
+    if let Some(statsd) = &args.statsd {
+        let client = StatsdClient::new(statsd, &args.statsd_prefix, &args.statsd_tag).await?;
+        runner = runner.with_recorder(client);
+    }
+    if let Some(influx) = &args.influx {
+        let exporter = InfluxExporter::new(
+            InfluxDestination::parse(influx),
+            Duration::from_secs(args.influx_interval),
+            &run_id,
+            &args.influx_tag,
+            runner.new_client()?,
+        )
+        .await?;
+        runner = runner.with_recorder(exporter);
+    }
+    if let Some(otlp_endpoint) = &args.otlp_endpoint {
+        let exporter =
+            OtlpExporter::new(otlp_endpoint, &args.otlp_service_name, runner.new_client()?);
+        runner = runner.with_recorder(exporter);
+    }
+    if args.template {
+        runner = runner.with_templating();
+    }
+    if let Some(compress_body) = args.compress_body {
+        runner = runner.with_body_compression(compress_body);
+    }
+    if args.stream_body {
+        runner = runner.with_body_streaming(BodyStreaming {
+            chunk_size: usize::try_from(args.chunk_size)?,
+            upload_rate: args.upload_rate,
+        });
+    }
+    if args.stream {
+        runner = runner.with_manifest_streaming(args.looping, args.block_size);
+    }
+    if args.virtual_users {
+        runner = runner.with_virtual_users(args.shared_connections);
+    }
+    if let Some(think_time) = args.think_time {
+        runner = runner.with_think_time(think_time);
+    }
+    if let Some(pacing) = args.pacing {
+        runner = runner.with_pacing(pacing);
+    }
+    if let Some(feeder) = &args.feeder {
+        runner = runner.with_feeder(Feeder::from_file(feeder, args.feeder_mode).await?);
+    }
+    if args.trace_context || args.otlp_endpoint.is_some() {
+        runner = runner.with_trace_context(args.trace_sample_ratio)?;
+    }
+    if let Some(request_id_header) = &args.request_id_header {
+        runner = runner.with_request_id(
+            HeaderName::from_str(request_id_header)?,
+            args.request_id_format,
+            &run_id,
+        );
+    }
     if args.debug {
         debug(&runner, &args).await?;
     } else {
-        run(&runner, &args).await?;
+        run(&runner, &args, curl.as_deref()).await?;
     }
     Ok(())
 }
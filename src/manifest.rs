@@ -0,0 +1,622 @@
+use crate::data_source::{DataSource, PickMode};
+use crate::form::{FormField, FormValue};
+use crate::input::{self, InputLines};
+use crate::template::Template;
+use crate::{HttpMethod, LoadTestRunner, Order, RequestTemplate};
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use rand::seq::SliceRandom;
+use reqwest::Url;
+use reqwest::header::{HeaderName, HeaderValue};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::sync::Mutex;
+
+/// Fields of a line of a manifest file.
+const FIELDS: [&str; 11] = [
+    "name",
+    "weight",
+    "method",
+    "url",
+    "path",
+    "query",
+    "headers",
+    "body",
+    "binary_body",
+    "form",
+    "form_urlencoded",
+];
+
+/// Maximum number of errors that are kept in a [`ManifestReport`].
+const MAX_ERRORS: usize = 100;
+
+/// Result of validating a manifest file with [`validate_manifest`].
+#[derive(Debug, Clone, Default)]
+pub struct ManifestReport {
+    /// Number of lines.
+    pub lines: usize,
+
+    /// Number of invalid lines.
+    pub invalid: usize,
+
+    /// Errors of the first invalid lines, with their line numbers.
+    pub errors: Vec<(usize, String)>,
+
+    /// Valid requests, keyed by the name their statistics are reported under.
+    pub mix: BTreeMap<String, RequestMix>,
+}
+
+/// Number and total weight of the requests with the same name in a manifest file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestMix {
+    /// Number of requests.
+    pub count: usize,
+
+    /// Sum of the weights of the requests.
+    pub weight: u64,
+}
+
+/// Validates every line of a manifest file without sending any requests.
+///
+/// Every line must be a JSON object with known fields of the right types, a valid method, URL,
+/// header names and values, and base64 `binary_body`. If templating is enabled, the `{{...}}`
+/// expressions must be valid and only use known variables. The file is read lazily, so huge
+/// manifests can be validated too.
+///
+/// # Parameters
+///
+/// * `path`: A manifest file.
+/// * `method`: HTTP method (GET, POST, etc.) of the requests without a method.
+/// * `variables`: Variables that are available to templates, e.g. the columns of a feeder, if
+///   templating is enabled.
+///
+/// # Returns
+///
+/// A `Result` containing the `ManifestReport` if the file could be read.
+pub async fn validate_manifest(
+    path: &Path,
+    method: HttpMethod,
+    variables: Option<&BTreeSet<String>>,
+) -> Result<ManifestReport> {
+    if !input::is_stdin(path) && !path.is_file() {
+        bail!(
+            "Manifest file '{}' does not exist or is not a file",
+            path.display()
+        );
+    }
+    let mut lines = input::lines(path).await?;
+    let mut report = ManifestReport::default();
+    while let Some(line) = lines.next_line().await? {
+        report.lines += 1;
+        match validate_line(&line, method, variables) {
+            Ok((name, weight)) => {
+                let mix = report.mix.entry(name).or_default();
+                mix.count += 1;
+                mix.weight += weight as u64;
+            }
+            Err(errors) => {
+                report.invalid += 1;
+                for error in errors {
+                    if report.errors.len() < MAX_ERRORS {
+                        report.errors.push((report.lines, error));
+                    }
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Validates the `{{...}}` expressions of a field if templating is enabled.
+///
+/// # Returns
+///
+/// `true` if the field is rendered as is, so its value can be validated.
+fn check_template(
+    errors: &mut Vec<String>,
+    variables: Option<&BTreeSet<String>>,
+    field: &str,
+    s: &str,
+) -> bool {
+    let Some(variables) = variables else {
+        return true;
+    };
+    match Template::parse(s) {
+        Ok(template) => {
+            for name in template.variables() {
+                if !variables.contains(name) {
+                    errors.push(format!("Unknown template variable '{name}' in {field}"));
+                }
+            }
+            template.is_static()
+        }
+        Err(e) => {
+            errors.push(format!("Invalid template in {field}: {e}"));
+            false
+        }
+    }
+}
+
+/// Validates a line of a manifest file.
+///
+/// # Returns
+///
+/// The name and the weight of the request, or every error of the line.
+fn validate_line(
+    line: &str,
+    method: HttpMethod,
+    variables: Option<&BTreeSet<String>>,
+) -> Result<(String, u32), Vec<String>> {
+    let mut value: Value = serde_json::from_str(line).map_err(|e| vec![e.to_string()])?;
+    let mut errors = Vec::new();
+    if let Some(object) = value.as_object_mut() {
+        // Unknown fields are removed after they are reported, so that the other fields are still
+        // checked although a request with unknown fields is rejected.
+        object.retain(|field, _| {
+            let known = FIELDS.contains(&field.as_str());
+            if !known {
+                errors.push(format!("Unknown field '{field}'"));
+            }
+            known
+        });
+    }
+    let template: RequestTemplate = serde_json::from_value(value).map_err(|e| {
+        errors.push(e.to_string());
+        errors.clone()
+    })?;
+    let method = match template.method.as_deref().map(HttpMethod::from_str) {
+        Some(Ok(method)) => method,
+        Some(Err(e)) => {
+            errors.push(e.to_string());
+            method
+        }
+        None => method,
+    };
+    if let Some(url) = &template.url
+        && check_template(&mut errors, variables, "url", url)
+        && let Err(e) = Url::parse(url)
+    {
+        errors.push(format!("Invalid URL '{url}': {e}"));
+    }
+    if let Some(path) = &template.path {
+        check_template(&mut errors, variables, "path", path);
+    }
+    for (name, value) in &template.query {
+        check_template(
+            &mut errors,
+            variables,
+            &format!("query parameter '{name}'"),
+            value,
+        );
+    }
+    for (name, value) in &template.headers {
+        if HeaderName::from_str(name).is_err() {
+            errors.push(format!("Invalid header name '{name}'"));
+        }
+        if check_template(&mut errors, variables, &format!("header '{name}'"), value)
+            && HeaderValue::from_str(value).is_err()
+        {
+            errors.push(format!("Invalid value of header '{name}'"));
+        }
+    }
+    if let Some(body) = &template.body {
+        check_template(&mut errors, variables, "body", body);
+    }
+    if let Some(binary_body) = &template.binary_body
+        && let Err(e) = BASE64_STANDARD.decode(binary_body)
+    {
+        errors.push(format!("Invalid base64 in binary_body: {e}"));
+    }
+    for (name, value) in &template.form {
+        match FormField::new(name, value).map(|field| field.value) {
+            Ok(FormValue::Text(value)) => {
+                check_template(
+                    &mut errors,
+                    variables,
+                    &format!("form field '{name}'"),
+                    &value,
+                );
+            }
+            Ok(FormValue::TextFile(path) | FormValue::File { path, .. }) if !path.is_file() => {
+                errors.push(format!(
+                    "File '{}' of form field '{name}' does not exist or is not a file",
+                    path.display()
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+    for (name, value) in &template.form_urlencoded {
+        check_template(
+            &mut errors,
+            variables,
+            &format!("form field '{name}'"),
+            value,
+        );
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let endpoint = match (&template.url, &template.path) {
+        (Some(url), _) => LoadTestRunner::endpoint(url),
+        (None, Some(path)) => {
+            LoadTestRunner::endpoint(&format!("/{}", path.trim_start_matches('/')))
+        }
+        (None, None) => "/".to_owned(),
+    };
+    let name = template
+        .name
+        .unwrap_or_else(|| format!("{method} {endpoint}"));
+    Ok((name, template.weight.unwrap_or(1)))
+}
+
+/// Default maximum number of lines of a streamed manifest in memory.
+pub const DEFAULT_BLOCK_SIZE: usize = 10000;
+
+/// Reads the requests of a manifest file lazily, so only a block of lines is in memory at a time.
+///
+/// The lines of every block are picked in order for [`Order::Sequential`], shuffled for
+/// [`Order::Random`], and sampled in proportion to their weights for [`Order::Weighted`]. The
+/// file is read once, or over and over again if it loops. A manifest from stdin is read once, so
+/// its requests can be generated on the fly.
+pub(crate) struct ManifestReader {
+    path: PathBuf,
+    order: Order,
+    looping: bool,
+    block_size: usize,
+    state: Mutex<ReaderState>,
+}
+
+struct ReaderState {
+    lines: InputLines,
+
+    /// Number of the last line read since the file was last opened.
+    line_number: usize,
+
+    /// Lines that are yet to be picked, with their line numbers.
+    block: VecDeque<(usize, String)>,
+
+    /// Number of lines picked in total.
+    picked: u64,
+}
+
+impl ManifestReader {
+    /// Opens a manifest file.
+    ///
+    /// # Parameters
+    ///
+    /// * `path`: A manifest file, or [`input::STDIN`].
+    /// * `order`: Order to pick the lines of a block in.
+    /// * `looping`: Whether to start over at the end of the file.
+    /// * `block_size`: Maximum number of lines in memory.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `ManifestReader` instance if successful.
+    pub(crate) async fn open(
+        path: &Path,
+        order: Order,
+        looping: bool,
+        block_size: usize,
+    ) -> Result<Self> {
+        if block_size == 0 {
+            bail!("Block size cannot be zero");
+        }
+        if input::is_stdin(path) {
+            if looping {
+                bail!("Manifest from stdin cannot loop");
+            }
+        } else if !path.is_file() {
+            bail!(
+                "Manifest file '{}' does not exist or is not a file",
+                path.display()
+            );
+        } else if path.metadata()?.len() == 0 {
+            bail!("Manifest file '{}' is empty", path.display());
+        }
+        let lines = input::lines(path).await?;
+        Ok(ManifestReader {
+            path: path.to_owned(),
+            order,
+            looping,
+            block_size,
+            state: Mutex::new(ReaderState {
+                lines,
+                line_number: 0,
+                block: VecDeque::new(),
+                picked: 0,
+            }),
+        })
+    }
+
+    /// Picks the next request.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the request, or an error if the line of the request is invalid or
+    /// the file has been read and does not loop.
+    pub(crate) async fn next(&self) -> Result<RequestTemplate> {
+        let mut state = self.state.lock().await;
+        if state.block.is_empty() {
+            self.read_block(&mut state).await?;
+        }
+        let Some((line_number, line)) = state.block.pop_front() else {
+            bail!(
+                "Manifest file '{}' exhausted after {} request(s)",
+                self.path.display(),
+                state.picked
+            );
+        };
+        state.picked += 1;
+        drop(state);
+        serde_json::from_str(&line).context(format!(
+            "Invalid request on line {line_number} of manifest file '{}'",
+            self.path.display()
+        ))
+    }
+
+    async fn read_block(&self, state: &mut ReaderState) -> Result<()> {
+        let mut lines = Vec::new();
+        while lines.len() < self.block_size {
+            match state.lines.next_line().await? {
+                Some(line) => {
+                    state.line_number += 1;
+                    lines.push((state.line_number, line));
+                }
+                // Only start over if the file still has lines.
+                None if self.looping && state.line_number > 0 => {
+                    state.lines = input::lines(&self.path).await?;
+                    state.line_number = 0;
+                }
+                None => break,
+            }
+        }
+        match self.order {
+            Order::Sequential => {}
+            Order::Random => lines.shuffle(&mut rand::rng()),
+            Order::Weighted if !lines.is_empty() => {
+                // Lines with an invalid request are picked with a weight of 1, so they fail when
+                // they are parsed.
+                let weights = lines
+                    .iter()
+                    .map(|(_, line)| {
+                        serde_json::from_str::<RequestTemplate>(line)
+                            .ok()
+                            .and_then(|template| template.weight)
+                            .unwrap_or(1)
+                    })
+                    .collect();
+                let len = lines.len();
+                let block = DataSource::with_weights(lines, weights, PickMode::Weighted).context(
+                    format!(
+                        "Invalid weights on lines {} to {} of manifest file '{}'",
+                        state.line_number.saturating_sub(len) + 1,
+                        state.line_number,
+                        self.path.display()
+                    ),
+                )?;
+                lines = (0..len)
+                    .map(|i| block.pick(i as u64).cloned())
+                    .collect::<Result<_>>()?;
+            }
+            Order::Weighted => {}
+        }
+        state.block = lines.into();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::fs;
+
+    async fn write_manifest(name: &str, lines: &[&str]) -> PathBuf {
+        // Every manifest gets its own directory, so that tests running at the same time do not
+        // overwrite each other's files.
+        let dir = std::env::temp_dir().join(format!("load-rs-manifest-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join(name);
+        fs::write(&path, lines.join("\n")).await.unwrap();
+        path
+    }
+
+    async fn paths(reader: &ManifestReader, n: usize) -> Vec<String> {
+        let mut paths = Vec::new();
+        for _ in 0..n {
+            paths.push(reader.next().await.unwrap().path.unwrap());
+        }
+        paths
+    }
+
+    #[tokio::test]
+    async fn next_sequential_succeeds() {
+        let path = write_manifest(
+            "sequential.jsonl",
+            &[
+                r#"{"path": "/a"}"#,
+                r#"{"path": "/b"}"#,
+                r#"{"path": "/c"}"#,
+            ],
+        )
+        .await;
+
+        let reader = ManifestReader::open(&path, Order::Sequential, false, 2)
+            .await
+            .unwrap();
+        assert_eq!(paths(&reader, 3).await, ["/a", "/b", "/c"]);
+        assert_eq!(
+            reader.next().await.unwrap_err().to_string(),
+            format!(
+                "Manifest file '{}' exhausted after 3 request(s)",
+                path.display()
+            )
+        );
+
+        let reader = ManifestReader::open(&path, Order::Sequential, true, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            paths(&reader, 7).await,
+            ["/a", "/b", "/c", "/a", "/b", "/c", "/a"]
+        );
+    }
+
+    #[tokio::test]
+    async fn next_random_picks_every_line_of_a_block() {
+        let lines: Vec<_> = (0..10).map(|i| format!(r#"{{"path": "/{i}"}}"#)).collect();
+        let lines: Vec<_> = lines.iter().map(String::as_str).collect();
+        let path = write_manifest("random.jsonl", &lines).await;
+
+        let reader = ManifestReader::open(&path, Order::Random, false, 5)
+            .await
+            .unwrap();
+        let mut paths = paths(&reader, 10).await;
+
+        // Lines are only shuffled within their block.
+        paths[..5].sort();
+        paths[5..].sort();
+        assert_eq!(
+            paths,
+            ["/0", "/1", "/2", "/3", "/4", "/5", "/6", "/7", "/8", "/9"]
+        );
+        assert!(reader.next().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn next_weighted_succeeds() {
+        let path = write_manifest(
+            "weighted.jsonl",
+            &[
+                r#"{"path": "/a", "weight": 0}"#,
+                r#"{"path": "/b", "weight": 3}"#,
+            ],
+        )
+        .await;
+
+        let reader = ManifestReader::open(&path, Order::Weighted, true, 2)
+            .await
+            .unwrap();
+
+        assert_eq!(paths(&reader, 6).await, ["/b"; 6]);
+    }
+
+    #[tokio::test]
+    async fn validate_manifest_succeeds() {
+        let path = write_manifest(
+            "valid.jsonl",
+            &[
+                r#"{"path": "/items?page={{seq}}", "weight": 3}"#,
+                r#"{"url": "http://localhost/items", "weight": 2}"#,
+                r#"{"name": "Order", "method": "POST", "headers": {"X-User": "{{user_id}}"}, "binary_body": "SGVsbG8="}"#,
+            ],
+        )
+        .await;
+
+        let report = validate_manifest(
+            &path,
+            HttpMethod::Get,
+            Some(&BTreeSet::from(["user_id".to_owned()])),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.lines, 3);
+        assert_eq!(report.invalid, 0);
+        assert_eq!(
+            report.mix,
+            BTreeMap::from([
+                (
+                    "GET /items".to_owned(),
+                    RequestMix {
+                        count: 2,
+                        weight: 5
+                    }
+                ),
+                (
+                    "Order".to_owned(),
+                    RequestMix {
+                        count: 1,
+                        weight: 1
+                    }
+                ),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_manifest_reports_errors() {
+        let path = write_manifest(
+            "invalid-lines.jsonl",
+            &[
+                r#"{"path": "/a"}"#,
+                r#"{"path": "/b""#,
+                r#"{"header": {"A": "1"}, "method": "FETCH", "weight": "high"}"#,
+                r#"{"url": "not a url", "headers": {"Bad Name": "1", "X-A": "a\u0001b"}}"#,
+                r#"{"body": "{{random_int 9 1}}", "binary_body": "%%%"}"#,
+                r#"{"form": {"file": "@missing.png", "title": "{{title}}"}, "form_urlencoded": {"q": "{{seq"}}"#,
+            ],
+        )
+        .await;
+
+        let report = validate_manifest(&path, HttpMethod::Get, Some(&BTreeSet::new()))
+            .await
+            .unwrap();
+
+        assert_eq!(report.lines, 6);
+        assert_eq!(report.invalid, 5);
+        let errors: Vec<_> = report
+            .errors
+            .iter()
+            .map(|(line, error)| format!("{line}: {error}"))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "2: EOF while parsing an object at line 1 column 13",
+                "3: Unknown field 'header'",
+                "3: invalid type: string \"high\", expected u32",
+                "4: Invalid URL 'not a url': relative URL without a base",
+                "4: Invalid header name 'Bad Name'",
+                "4: Invalid value of header 'X-A'",
+                "5: Invalid template in body: Invalid 'random_int' range: 9 is greater than 1",
+                "5: Invalid base64 in binary_body: Invalid symbol 37, offset 0.",
+                "6: File 'missing.png' of form field 'file' does not exist or is not a file",
+                "6: Unknown template variable 'title' in form field 'title'",
+                "6: Invalid template in form field 'q': Unclosed template expression in '{{seq'",
+            ]
+        );
+        assert_eq!(report.mix.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn open_stdin_looping_fails() {
+        let err = ManifestReader::open(Path::new(input::STDIN), Order::Sequential, true, 2)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "Manifest from stdin cannot loop");
+    }
+
+    #[tokio::test]
+    async fn next_invalid_line_fails() {
+        let path = write_manifest("invalid.jsonl", &[r#"{"path": "/a"}"#, "{"]).await;
+
+        let reader = ManifestReader::open(&path, Order::Sequential, false, 10)
+            .await
+            .unwrap();
+
+        assert!(reader.next().await.is_ok());
+        assert_eq!(
+            reader.next().await.unwrap_err().to_string(),
+            format!(
+                "Invalid request on line 2 of manifest file '{}'",
+                path.display()
+            )
+        );
+    }
+}
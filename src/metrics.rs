@@ -0,0 +1,56 @@
+use crate::trace::TraceContext;
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::fmt::Debug;
+use std::time::{Duration, SystemTime};
+
+/// A single completed request as seen by a [`Recorder`].
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// Name the request is reported under, e.g. `GET /items` or the name of a manifest entry.
+    pub name: String,
+
+    /// Endpoint the request was sent to, e.g. the URL path.
+    pub endpoint: String,
+
+    /// HTTP status code of the response, if a response was received.
+    pub status: Option<u16>,
+
+    /// Whether the request was successful.
+    pub success: bool,
+
+    /// Duration of the request.
+    pub duration: Duration,
+
+    /// Time at which the request completed.
+    pub timestamp: SystemTime,
+
+    /// W3C trace context of the request, if trace context injection is enabled.
+    pub trace: Option<TraceContext>,
+}
+
+impl Sample {
+    /// Returns the status code as a label value, or `error` if no response was received.
+    pub fn status_label(&self) -> String {
+        self.status
+            .map(|status| status.to_string())
+            .unwrap_or_else(|| "error".to_owned())
+    }
+}
+
+/// A sink for live metrics that is fed every completed request of a load test run.
+pub trait Recorder: Debug + Send + Sync {
+    /// Records a completed request.
+    fn record(&self, sample: &Sample);
+
+    /// Flushes any buffered metrics once the run has completed.
+    fn finish(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Returns the number of samples whose metrics could not be exported, e.g. because the
+    /// destination was unreachable.
+    fn dropped(&self) -> u64 {
+        0
+    }
+}
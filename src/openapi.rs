@@ -0,0 +1,784 @@
+use crate::{HttpMethod, RequestTemplate};
+use anyhow::{Context, Result, bail};
+use reqwest::Url;
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use tokio::fs;
+
+/// Maximum number of nested `$ref`s to inline, which bounds recursive schemas.
+const MAX_REF_DEPTH: usize = 16;
+
+/// Methods of a path item that are operations, in the order they are enumerated.
+const METHODS: [&str; 6] = ["get", "post", "put", "patch", "delete", "head"];
+
+/// An operation of an [`OpenApi`] document.
+#[derive(Debug, Clone)]
+pub struct Operation {
+    /// The `operationId`, or the method and the path if the operation has no ID.
+    pub name: String,
+
+    pub(crate) request: RequestTemplate,
+
+    /// Schemas of the JSON responses, keyed by status code, e.g. `200`, `2XX` or `default`.
+    responses: BTreeMap<String, Option<Value>>,
+}
+
+impl Operation {
+    /// Returns the operation as a line of a request manifest.
+    pub fn to_manifest_line(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.request)?)
+    }
+
+    /// Validates a response against the declared response schema of its status code.
+    ///
+    /// # Parameters
+    ///
+    /// * `status`: Status code of the response.
+    /// * `body`: Body of the response.
+    ///
+    /// # Returns
+    ///
+    /// An error if the status code is not declared or the body does not match the schema.
+    pub fn validate(&self, status: u16, body: &[u8]) -> Result<()> {
+        if self.responses.is_empty() {
+            return Ok(());
+        }
+        let status = status.to_string();
+        let range = format!("{}XX", &status[..1]);
+        let schema = self
+            .responses
+            .iter()
+            .find(|(key, _)| **key == status)
+            .or_else(|| {
+                self.responses
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(&range))
+            })
+            .or_else(|| self.responses.iter().find(|(key, _)| *key == "default"))
+            .map(|(_, schema)| schema)
+            .context(format!("Response status {status} is not declared"))?;
+        let Some(schema) = schema else {
+            return Ok(());
+        };
+        let value: Value =
+            serde_json::from_slice(body).context("Response body is not valid JSON")?;
+        if let Err(e) = validate(&value, schema, "$") {
+            bail!("Response body does not match the schema: {e}");
+        }
+        Ok(())
+    }
+}
+
+/// The operations of an OpenAPI 3 document.
+#[derive(Debug, Clone)]
+pub struct OpenApi {
+    operations: Vec<Operation>,
+}
+
+impl OpenApi {
+    /// Reads an OpenAPI 3 document.
+    ///
+    /// Files with a `.yaml` or `.yml` extension are read as YAML, and any other file is read as
+    /// JSON. Every operation becomes a request with the parameters and the body from the examples
+    /// of the document, or generated from the schemas if there are no examples. Path parameters,
+    /// and required query and header parameters are always set, and optional query parameters
+    /// are only set if they have an example. The path of the first server URL, e.g. `/v1`, is
+    /// prepended to the path of every operation.
+    ///
+    /// # Parameters
+    ///
+    /// * `path`: OpenAPI document.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `OpenApi` instance if successful.
+    pub async fn from_file(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            bail!(
+                "OpenAPI document '{}' does not exist or is not a file",
+                path.display()
+            );
+        }
+        let content = fs::read_to_string(path).await?;
+        let document: Value = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+            _ => serde_json::from_str(&content)?,
+        };
+        let openapi = Self::parse(&document)
+            .context(format!("Invalid OpenAPI document '{}'", path.display()))?;
+        if openapi.operations.is_empty() {
+            bail!(
+                "OpenAPI document '{}' does not contain any operations",
+                path.display()
+            );
+        }
+        Ok(openapi)
+    }
+
+    fn parse(document: &Value) -> Result<Self> {
+        let version = document
+            .get("openapi")
+            .and_then(Value::as_str)
+            .context("Missing 'openapi' version")?;
+        if !version.starts_with("3.") {
+            bail!("Unsupported OpenAPI version '{version}'");
+        }
+        let base_path = document
+            .pointer("/servers/0/url")
+            .and_then(Value::as_str)
+            .map(|url| match Url::parse(url) {
+                Ok(url) => url.path().to_owned(),
+                Err(_) => url.to_owned(),
+            })
+            .unwrap_or_default();
+        let mut operations = Vec::new();
+        let paths = document
+            .get("paths")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+        for (path, item) in &paths {
+            let item = resolve(document, item)?;
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                let operation =
+                    Self::parse_operation(document, &base_path, path, method, item, operation)
+                        .context(format!(
+                            "Invalid operation {} {path}",
+                            method.to_ascii_uppercase()
+                        ))?;
+                operations.push(operation);
+            }
+        }
+        Ok(OpenApi { operations })
+    }
+
+    fn parse_operation(
+        document: &Value,
+        base_path: &str,
+        path: &str,
+        method: &str,
+        item: &Value,
+        operation: &Value,
+    ) -> Result<Operation> {
+        let method = HttpMethod::from_str(method)?.to_string();
+        let name = operation
+            .get("operationId")
+            .and_then(Value::as_str)
+            .map_or_else(|| format!("{method} {path}"), str::to_owned);
+        // Parameters of the operation override the parameters of the path item.
+        let mut parameters: BTreeMap<(String, String), &Value> = BTreeMap::new();
+        for parameter in [item, operation]
+            .iter()
+            .filter_map(|v| v.get("parameters").and_then(Value::as_array))
+            .flatten()
+        {
+            let parameter = resolve(document, parameter)?;
+            let (Some(name), Some(location)) = (
+                parameter.get("name").and_then(Value::as_str),
+                parameter.get("in").and_then(Value::as_str),
+            ) else {
+                bail!("Parameter without a 'name' or 'in'");
+            };
+            parameters.insert((location.to_owned(), name.to_owned()), parameter);
+        }
+        let mut request = RequestTemplate {
+            name: Some(name.clone()),
+            method: Some(method),
+            ..RequestTemplate::default()
+        };
+        let mut path = format!("{}{path}", base_path.trim_end_matches('/'));
+        for ((location, name), parameter) in parameters {
+            let required = parameter.get("required").and_then(Value::as_bool) == Some(true);
+            let explicit = explicit_example(parameter)
+                .or_else(|| parameter.get("schema").and_then(explicit_example));
+            if location != "path" && !required && explicit.is_none() {
+                continue;
+            }
+            let value = match explicit {
+                Some(value) => value,
+                None => {
+                    let schema =
+                        inline(document, parameter.get("schema").unwrap_or(&Value::Null), 0)?;
+                    example(&schema)
+                }
+            };
+            let value = to_parameter(&value);
+            match location.as_str() {
+                "path" => path = path.replace(&format!("{{{name}}}"), &value),
+                "query" => {
+                    request.query.insert(name, value);
+                }
+                "header" => {
+                    request.headers.insert(name, value);
+                }
+                _ => {}
+            }
+        }
+        request.path = Some(path);
+        if let Some(body) = operation.get("requestBody") {
+            let body = resolve(document, body)?;
+            if let Some((content_type, media)) = body
+                .get("content")
+                .and_then(Value::as_object)
+                .and_then(|content| {
+                    content
+                        .iter()
+                        .find(|(content_type, _)| is_json(content_type))
+                        .or_else(|| content.iter().next())
+                })
+            {
+                let value = match explicit_example(media) {
+                    Some(value) => value,
+                    None => example(&inline(
+                        document,
+                        media.get("schema").unwrap_or(&Value::Null),
+                        0,
+                    )?),
+                };
+                request
+                    .headers
+                    .insert("Content-Type".to_owned(), content_type.clone());
+                request.body = Some(to_body(content_type, &value));
+            }
+        }
+        let mut responses = BTreeMap::new();
+        if let Some(declared) = operation.get("responses").and_then(Value::as_object) {
+            for (status, response) in declared {
+                let response = resolve(document, response)?;
+                let schema = response
+                    .get("content")
+                    .and_then(Value::as_object)
+                    .and_then(|content| content.iter().find(|(t, _)| is_json(t)))
+                    .and_then(|(_, media)| media.get("schema"))
+                    .map(|schema| inline(document, schema, 0))
+                    .transpose()?;
+                responses.insert(status.clone(), schema);
+            }
+        }
+        Ok(Operation {
+            name,
+            request,
+            responses,
+        })
+    }
+
+    /// Sets the weights of operations by name, where operations without a weight have a weight
+    /// of 1, and operations with a weight of 0 are removed.
+    ///
+    /// # Parameters
+    ///
+    /// * `weights`: Weights of the operations, keyed by the operation name.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `OpenApi` with the weights set if successful.
+    pub fn with_weights(mut self, weights: &BTreeMap<String, u32>) -> Result<Self> {
+        for name in weights.keys() {
+            if !self
+                .operations
+                .iter()
+                .any(|operation| operation.name == *name)
+            {
+                bail!("Unknown operation '{name}'");
+            }
+        }
+        self.operations
+            .retain(|operation| weights.get(&operation.name) != Some(&0));
+        for operation in &mut self.operations {
+            operation.request.weight = weights.get(&operation.name).copied();
+        }
+        if self.operations.is_empty() {
+            bail!("All operations have a weight of 0");
+        }
+        Ok(self)
+    }
+
+    /// Returns the operations of the document.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+}
+
+/// Follows a local `$ref`, e.g. `#/components/parameters/id`.
+fn resolve<'a>(document: &'a Value, value: &'a Value) -> Result<&'a Value> {
+    let mut value = value;
+    for _ in 0..MAX_REF_DEPTH {
+        let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+            return Ok(value);
+        };
+        let pointer = reference
+            .strip_prefix('#')
+            .context(format!("Unsupported reference '{reference}'"))?;
+        value = document
+            .pointer(pointer)
+            .context(format!("Unknown reference '{reference}'"))?;
+    }
+    bail!("Too many nested references");
+}
+
+/// Inlines the `$ref`s of a schema. A schema that is nested too deeply, e.g. a recursive schema,
+/// becomes an empty schema, which matches any value.
+fn inline(document: &Value, schema: &Value, depth: usize) -> Result<Value> {
+    if depth > MAX_REF_DEPTH {
+        return Ok(json!({}));
+    }
+    Ok(match schema {
+        Value::Object(object) => {
+            if object.contains_key("$ref") {
+                return inline(document, resolve(document, schema)?, depth + 1);
+            }
+            let mut inlined = Map::new();
+            for (key, value) in object {
+                // Examples are values rather than schemas.
+                let value = if key == "example" || key == "examples" || key == "default" {
+                    value.clone()
+                } else {
+                    inline(document, value, depth)?
+                };
+                inlined.insert(key.clone(), value);
+            }
+            Value::Object(inlined)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| inline(document, item, depth))
+                .collect::<Result<_>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+/// Returns the `example`, or the first of the `examples`, of a parameter, a media type or a
+/// schema.
+fn explicit_example(value: &Value) -> Option<Value> {
+    if let Some(example) = value.get("example") {
+        return Some(example.clone());
+    }
+    match value.get("examples")? {
+        Value::Array(examples) => examples.first().cloned(),
+        Value::Object(examples) => examples
+            .values()
+            .next()
+            .and_then(|example| example.get("value"))
+            .cloned(),
+        _ => None,
+    }
+}
+
+/// Generates an example value of an inlined schema.
+fn example(schema: &Value) -> Value {
+    if let Some(example) = explicit_example(schema) {
+        return example;
+    }
+    if let Some(value) = schema.get("default").or_else(|| schema.get("const")) {
+        return value.clone();
+    }
+    if let Some(value) = schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .and_then(|values| values.first())
+    {
+        return value.clone();
+    }
+    if let Some(schemas) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = Map::new();
+        for schema in schemas {
+            match example(schema) {
+                Value::Object(object) => merged.extend(object),
+                value => return value,
+            }
+        }
+        return Value::Object(merged);
+    }
+    if let Some(schema) = ["oneOf", "anyOf"]
+        .iter()
+        .find_map(|key| schema.get(key)?.as_array()?.first())
+    {
+        return example(schema);
+    }
+    let number = |default: f64| {
+        schema
+            .get("minimum")
+            .and_then(Value::as_f64)
+            .or_else(|| {
+                schema
+                    .get("maximum")
+                    .and_then(Value::as_f64)
+                    .filter(|maximum| *maximum < default)
+            })
+            .unwrap_or(default)
+    };
+    match schema_type(schema) {
+        Some("object") => Value::Object(
+            schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .map(|(name, schema)| (name.clone(), example(schema)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+        Some("array") => json!([example(schema.get("items").unwrap_or(&Value::Null))]),
+        Some("string") => json!(match schema.get("format").and_then(Value::as_str) {
+            Some("date-time") => "2025-01-01T00:00:00Z",
+            Some("date") => "2025-01-01",
+            Some("email") => "user@example.com",
+            Some("uuid") => "00000000-0000-4000-8000-000000000000",
+            Some("uri" | "url") => "https://example.com",
+            Some("byte") => "ZXhhbXBsZQ==",
+            _ => "string",
+        }),
+        Some("integer") => json!(number(1.0) as i64),
+        Some("number") => json!(number(1.0)),
+        Some("boolean") => json!(true),
+        _ => Value::Null,
+    }
+}
+
+/// Returns the type of a schema, which is the first type other than `null` if there are several
+/// types, or `object` if the schema has properties.
+fn schema_type(schema: &Value) -> Option<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => Some(t),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null"),
+        _ if schema.get("properties").is_some() => Some("object"),
+        _ => None,
+    }
+}
+
+/// Validates a value against an inlined schema, which supports the `type`, `nullable`, `enum`,
+/// `const`, `properties`, `required`, `items`, `allOf`, `anyOf` and `oneOf` keywords.
+fn validate(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    if value.is_null() && schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+        return Ok(());
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array)
+        && !values.contains(value)
+    {
+        return Err(format!(
+            "{path}: {value} is not one of {}",
+            Value::from(values.clone())
+        ));
+    }
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        return Err(format!("{path}: expected {expected} but got {value}"));
+    }
+    if let Some(schemas) = schema.get("allOf").and_then(Value::as_array) {
+        for schema in schemas {
+            validate(value, schema, path)?;
+        }
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(schemas) = schema.get(key).and_then(Value::as_array) {
+            let mut errors = schemas.iter().map(|schema| validate(value, schema, path));
+            if !errors.any(|result| result.is_ok()) {
+                return Err(format!("{path}: does not match any schema of '{key}'"));
+            }
+        }
+    }
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+        return Err(format!(
+            "{path}: expected {} but got {}",
+            types.join(" or "),
+            type_name(value)
+        ));
+    }
+    if let Value::Object(object) = value {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    return Err(format!("{path}: missing required property '{name}'"));
+                }
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, schema) in properties {
+                if let Some(value) = object.get(name) {
+                    validate(value, schema, &format!("{path}.{name}"))?;
+                }
+            }
+        }
+    }
+    if let (Value::Array(items), Some(schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate(item, schema, &format!("{path}[{i}]"))?;
+        }
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, t: &str) -> bool {
+    match t {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_json(content_type: &str) -> bool {
+    let content_type = content_type.to_ascii_lowercase();
+    content_type.starts_with("application/json") || content_type.contains("+json")
+}
+
+/// Formats a parameter value, where the items of an array are separated by commas.
+fn to_parameter(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(to_parameter).collect::<Vec<_>>().join(","),
+        value => value.to_string(),
+    }
+}
+
+fn to_body(content_type: &str, value: &Value) -> String {
+    match (value, content_type) {
+        (Value::Object(fields), "application/x-www-form-urlencoded") => {
+            let mut form = Url::parse("http://localhost").expect("Valid URL");
+            form.query_pairs_mut().extend_pairs(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name, to_parameter(value))),
+            );
+            form.query().unwrap_or_default().to_owned()
+        }
+        (Value::String(s), content_type) if !is_json(content_type) => s.clone(),
+        (value, _) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> Value {
+        serde_yaml::from_str(
+            r##"
+openapi: 3.0.3
+servers:
+  - url: https://api.example.com/v1
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      parameters:
+        - name: limit
+          in: query
+          schema: {type: integer, minimum: 10}
+        - name: tag
+          in: query
+          example: cat
+        - name: X-Tenant
+          in: header
+          required: true
+          schema: {type: string, format: uuid}
+      responses:
+        "200":
+          description: Pets
+          content:
+            application/json:
+              schema:
+                type: array
+                items: {$ref: "#/components/schemas/Pet"}
+    post:
+      operationId: createPet
+      requestBody:
+        content:
+          application/json:
+            schema: {$ref: "#/components/schemas/NewPet"}
+      responses:
+        "201":
+          description: Created
+          content:
+            application/json:
+              schema: {$ref: "#/components/schemas/Pet"}
+        default:
+          description: Error
+  /pets/{petId}:
+    parameters:
+      - $ref: "#/components/parameters/PetId"
+    delete:
+      responses:
+        "204": {description: Deleted}
+components:
+  parameters:
+    PetId:
+      name: petId
+      in: path
+      required: true
+      schema: {type: integer, example: 7}
+  schemas:
+    NewPet:
+      type: object
+      required: [name]
+      properties:
+        name: {type: string, example: Rex}
+        kind: {type: string, enum: [dog, cat]}
+        born: {type: string, format: date}
+    Pet:
+      allOf:
+        - $ref: "#/components/schemas/NewPet"
+        - type: object
+          required: [id]
+          properties:
+            id: {type: integer}
+            parent: {$ref: "#/components/schemas/Pet"}
+"##,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_succeeds() {
+        let openapi = OpenApi::parse(&document()).unwrap();
+
+        let lines: Vec<_> = openapi
+            .operations()
+            .iter()
+            .map(|operation| operation.to_manifest_line().unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"name":"listPets","method":"GET","path":"/v1/pets","query":{"tag":"cat"},"headers":{"X-Tenant":"00000000-0000-4000-8000-000000000000"}}"#,
+                r#"{"name":"createPet","method":"POST","path":"/v1/pets","headers":{"Content-Type":"application/json"},"body":"{\"born\":\"2025-01-01\",\"kind\":\"dog\",\"name\":\"Rex\"}"}"#,
+                r#"{"name":"DELETE /pets/{petId}","method":"DELETE","path":"/v1/pets/7"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_document_fails() {
+        assert_eq!(
+            OpenApi::parse(&json!({"swagger": "2.0"}))
+                .unwrap_err()
+                .to_string(),
+            "Missing 'openapi' version"
+        );
+        let err = OpenApi::parse(&json!({
+            "openapi": "3.1.0",
+            "paths": {"/a": {"get": {"parameters": [{"$ref": "#/missing"}]}}}
+        }))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid operation GET /a");
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Unknown reference '#/missing'"
+        );
+    }
+
+    #[test]
+    fn with_weights_succeeds() {
+        let openapi = OpenApi::parse(&document())
+            .unwrap()
+            .with_weights(&BTreeMap::from([
+                ("listPets".to_owned(), 5),
+                ("DELETE /pets/{petId}".to_owned(), 0),
+            ]))
+            .unwrap();
+
+        assert_eq!(openapi.operations().len(), 2);
+        assert_eq!(openapi.operations()[0].request.weight, Some(5));
+        assert_eq!(openapi.operations()[1].request.weight, None);
+        assert_eq!(
+            OpenApi::parse(&document())
+                .unwrap()
+                .with_weights(&BTreeMap::from([("x".to_owned(), 1)]))
+                .unwrap_err()
+                .to_string(),
+            "Unknown operation 'x'"
+        );
+    }
+
+    #[test]
+    fn validate_succeeds() {
+        let openapi = OpenApi::parse(&document()).unwrap();
+        let list = &openapi.operations()[0];
+        let create = &openapi.operations()[1];
+        let delete = &openapi.operations()[2];
+
+        assert!(
+            list.validate(
+                200,
+                br#"[{"id": 1, "name": "Rex", "parent": {"id": 2, "name": "Max"}}]"#
+            )
+            .is_ok()
+        );
+        assert!(
+            create
+                .validate(201, br#"{"id": 1, "name": "Rex", "kind": "cat"}"#)
+                .is_ok()
+        );
+        assert!(create.validate(500, b"Internal Server Error").is_ok());
+        assert!(delete.validate(204, b"").is_ok());
+    }
+
+    #[test]
+    fn validate_fails() {
+        let openapi = OpenApi::parse(&document()).unwrap();
+        let list = &openapi.operations()[0];
+        let create = &openapi.operations()[1];
+        let delete = &openapi.operations()[2];
+
+        assert_eq!(
+            list.validate(200, br#"[{"id": "1", "name": "Rex"}]"#)
+                .unwrap_err()
+                .to_string(),
+            "Response body does not match the schema: $[0].id: expected integer but got string"
+        );
+        assert_eq!(
+            create
+                .validate(201, br#"{"id": 1}"#)
+                .unwrap_err()
+                .to_string(),
+            "Response body does not match the schema: $: missing required property 'name'"
+        );
+        assert_eq!(
+            create
+                .validate(201, br#"{"id": 1, "name": "Rex", "kind": "bird"}"#)
+                .unwrap_err()
+                .to_string(),
+            r#"Response body does not match the schema: $.kind: "bird" is not one of ["dog","cat"]"#
+        );
+        assert_eq!(
+            delete.validate(200, b"").unwrap_err().to_string(),
+            "Response status 200 is not declared"
+        );
+        assert!(list.validate(200, b"<html>").is_err());
+    }
+}
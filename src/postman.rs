@@ -0,0 +1,760 @@
+use crate::RequestTemplate;
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use tokio::fs;
+
+/// Maximum number of times a value is resolved, which bounds variables that refer to each other.
+const MAX_RESOLVE_DEPTH: usize = 8;
+
+/// Dynamic variables of Postman that have an equivalent [template](crate::template::Template)
+/// expression.
+const DYNAMIC_VARIABLES: [(&str, &str); 5] = [
+    ("$guid", "uuid"),
+    ("$randomUUID", "uuid"),
+    ("$isoTimestamp", "now_rfc3339"),
+    ("$randomInt", "random_int 0 1000"),
+    ("$randomAlphaNumeric", "random_string 1"),
+];
+
+/// JSON representation of a Postman collection (v2.0 or v2.1).
+#[derive(Debug, Deserialize)]
+struct CollectionFile {
+    info: Option<Value>,
+    #[serde(default)]
+    item: Vec<Item>,
+    #[serde(default)]
+    variable: Vec<KeyValue>,
+    auth: Option<Auth>,
+    #[serde(default)]
+    event: Vec<Event>,
+}
+
+/// A request, or a folder of items if it has `item`s.
+#[derive(Debug, Deserialize)]
+struct Item {
+    #[serde(default)]
+    name: String,
+    item: Option<Vec<Item>>,
+    request: Option<RequestValue>,
+    auth: Option<Auth>,
+    #[serde(default)]
+    event: Vec<Event>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RequestValue {
+    Url(String),
+    Request(Box<Request>),
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    method: Option<String>,
+    url: Option<UrlValue>,
+    #[serde(default)]
+    header: HeaderValue,
+    body: Option<RequestBody>,
+    auth: Option<Auth>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum UrlValue {
+    Raw(String),
+    Url(Box<PostmanUrl>),
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanUrl {
+    raw: Option<String>,
+    protocol: Option<String>,
+    host: Option<StringOrList>,
+    port: Option<String>,
+    path: Option<StringOrList>,
+    #[serde(default)]
+    query: Vec<KeyValue>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    String(String),
+    List(Vec<Value>),
+}
+
+impl StringOrList {
+    fn join(&self, separator: &str) -> String {
+        match self {
+            StringOrList::String(s) => s.clone(),
+            StringOrList::List(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    Value::String(s) => s.clone(),
+                    part => part
+                        .get("value")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_owned(),
+                })
+                .collect::<Vec<_>>()
+                .join(separator),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(untagged)]
+enum HeaderValue {
+    #[default]
+    None,
+    Raw(String),
+    List(Vec<KeyValue>),
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyValue {
+    #[serde(alias = "id")]
+    key: Option<String>,
+    value: Option<Value>,
+    #[serde(default)]
+    disabled: bool,
+    enabled: Option<bool>,
+}
+
+impl KeyValue {
+    fn is_enabled(&self) -> bool {
+        !self.disabled && self.enabled != Some(false)
+    }
+
+    fn value(&self) -> String {
+        match &self.value {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(value) => value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestBody {
+    mode: Option<String>,
+    raw: Option<String>,
+    #[serde(default)]
+    urlencoded: Vec<KeyValue>,
+    graphql: Option<GraphQl>,
+    options: Option<Value>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQl {
+    query: Option<String>,
+    variables: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Auth {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(flatten)]
+    attributes: BTreeMap<String, Value>,
+}
+
+impl Auth {
+    /// Returns an attribute of the auth, e.g. the `token` of `bearer`, which is either a list of
+    /// key-value pairs (v2.1) or an object (v2.0).
+    fn attribute(&self, name: &str) -> Option<String> {
+        let attributes = self.attributes.get(&self.kind)?;
+        let value = match attributes {
+            Value::Array(pairs) => pairs
+                .iter()
+                .find(|pair| pair.get("key").and_then(Value::as_str) == Some(name))?
+                .get("value")?,
+            Value::Object(object) => object.get(name)?,
+            _ => return None,
+        };
+        Some(match value {
+            Value::String(s) => s.clone(),
+            value => value.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    listen: String,
+    script: Option<Script>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Script {
+    exec: Option<StringOrList>,
+}
+
+impl Event {
+    fn is_pre_request_script(&self) -> bool {
+        self.listen == "prerequest"
+            && !self.disabled
+            && self
+                .script
+                .as_ref()
+                .and_then(|script| script.exec.as_ref())
+                .is_some_and(|exec| !exec.join("\n").trim().is_empty())
+    }
+}
+
+/// JSON representation of a Postman environment.
+#[derive(Debug, Deserialize)]
+struct EnvironmentFile {
+    #[serde(default)]
+    values: Vec<KeyValue>,
+}
+
+/// The requests of a Postman collection.
+#[derive(Debug, Clone)]
+pub struct PostmanCollection {
+    requests: Vec<RequestTemplate>,
+    warnings: Vec<String>,
+}
+
+impl PostmanCollection {
+    /// Reads a Postman collection (v2.0 or v2.1), and optionally a Postman environment.
+    ///
+    /// Every request of the collection, including the requests in folders, becomes a request
+    /// with the method, URL, headers and body of the request, and is named by its folders and its
+    /// name, e.g. `Orders / Create order`. The `{{name}}` variables are replaced with the values
+    /// of the environment or the collection, in that order of precedence, and the dynamic
+    /// variables `{{$guid}}`, `{{$randomUUID}}`, `{{$isoTimestamp}}`, `{{$randomInt}}` and
+    /// `{{$randomAlphaNumeric}}` become template expressions. The bearer, basic and API key auth
+    /// of a request, or else of its folders or the collection, becomes a header or a query
+    /// parameter.
+    ///
+    /// Anything that cannot be translated, e.g. a pre-request script or an unknown variable, is
+    /// reported as a warning.
+    ///
+    /// # Parameters
+    ///
+    /// * `path`: Postman collection file.
+    /// * `environment`: Postman environment file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `PostmanCollection` instance if successful.
+    pub async fn from_file(path: &Path, environment: Option<&Path>) -> Result<Self> {
+        if !path.is_file() {
+            bail!(
+                "Postman collection '{}' does not exist or is not a file",
+                path.display()
+            );
+        }
+        let content = fs::read_to_string(path).await?;
+        let environment = match environment {
+            Some(environment) => {
+                if !environment.is_file() {
+                    bail!(
+                        "Postman environment '{}' does not exist or is not a file",
+                        environment.display()
+                    );
+                }
+                let content = fs::read_to_string(environment).await?;
+                let environment: EnvironmentFile = serde_json::from_str(&content).context(
+                    format!("Invalid Postman environment '{}'", environment.display()),
+                )?;
+                environment.values
+            }
+            None => Vec::new(),
+        };
+        let collection = Self::parse(&content, environment)
+            .context(format!("Invalid Postman collection '{}'", path.display()))?;
+        if collection.requests.is_empty() {
+            bail!(
+                "Postman collection '{}' does not contain any requests",
+                path.display()
+            );
+        }
+        Ok(collection)
+    }
+
+    fn parse(content: &str, environment: Vec<KeyValue>) -> Result<Self> {
+        let collection: CollectionFile = serde_json::from_str(content)?;
+        if let Some(schema) = collection
+            .info
+            .as_ref()
+            .and_then(|info| info.get("schema"))
+            .and_then(Value::as_str)
+            && !schema.contains("v2.0")
+            && !schema.contains("v2.1")
+        {
+            bail!("Unsupported collection schema '{schema}'");
+        }
+        let mut variables = BTreeMap::new();
+        for variable in collection.variable.iter().chain(&environment) {
+            if let Some(key) = &variable.key
+                && variable.is_enabled()
+            {
+                variables.insert(key.clone(), variable.value());
+            }
+        }
+        let mut importer = Importer {
+            variables,
+            unknown: BTreeSet::new(),
+            requests: Vec::new(),
+            warnings: Vec::new(),
+        };
+        if collection.event.iter().any(Event::is_pre_request_script) {
+            importer
+                .warnings
+                .push("The pre-request script of the collection cannot be translated".to_owned());
+        }
+        importer.import_items(&collection.item, &[], collection.auth.as_ref());
+        let Importer {
+            requests,
+            mut warnings,
+            unknown,
+            ..
+        } = importer;
+        for name in unknown {
+            warnings.push(format!(
+                "Unknown variable '{{{{{name}}}}}' is kept as a template variable"
+            ));
+        }
+        Ok(PostmanCollection { requests, warnings })
+    }
+
+    /// Returns the requests of the collection as lines of a request manifest.
+    pub fn to_manifest_lines(&self) -> Result<Vec<String>> {
+        self.requests
+            .iter()
+            .map(|request| Ok(serde_json::to_string(request)?))
+            .collect()
+    }
+
+    /// Returns what could not be translated from the collection.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns the number of requests.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns `true` if there are no requests.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+/// Converts the items of a collection into requests.
+struct Importer {
+    variables: BTreeMap<String, String>,
+    unknown: BTreeSet<String>,
+    requests: Vec<RequestTemplate>,
+    warnings: Vec<String>,
+}
+
+impl Importer {
+    fn import_items(&mut self, items: &[Item], folders: &[&str], auth: Option<&Auth>) {
+        for item in items {
+            let mut path = folders.to_vec();
+            path.push(&item.name);
+            let name = path.join(" / ");
+            let auth = item.auth.as_ref().or(auth);
+            if item.event.iter().any(Event::is_pre_request_script) {
+                self.warnings.push(format!(
+                    "The pre-request script of '{name}' cannot be translated"
+                ));
+            }
+            if let Some(items) = &item.item {
+                self.import_items(items, &path, auth);
+            } else if let Some(request) = &item.request {
+                let request = self.import_request(&name, request, auth);
+                self.requests.push(request);
+            }
+        }
+    }
+
+    fn import_request(
+        &mut self,
+        name: &str,
+        request: &RequestValue,
+        auth: Option<&Auth>,
+    ) -> RequestTemplate {
+        let request = match request {
+            RequestValue::Url(url) => {
+                return RequestTemplate {
+                    name: Some(name.to_owned()),
+                    method: Some("GET".to_owned()),
+                    url: Some(self.resolve(url)),
+                    ..RequestTemplate::default()
+                };
+            }
+            RequestValue::Request(request) => request,
+        };
+        let mut template = RequestTemplate {
+            name: Some(name.to_owned()),
+            method: Some(
+                request
+                    .method
+                    .as_deref()
+                    .unwrap_or("GET")
+                    .to_ascii_uppercase(),
+            ),
+            ..RequestTemplate::default()
+        };
+        let mut url = match &request.url {
+            Some(UrlValue::Raw(url)) => self.resolve(url),
+            Some(UrlValue::Url(url)) => self.import_url(url),
+            None => String::new(),
+        };
+        match &request.header {
+            HeaderValue::None => {}
+            HeaderValue::Raw(headers) => {
+                for (name, value) in headers.lines().filter_map(|line| line.split_once(':')) {
+                    let (name, value) = (self.resolve(name.trim()), self.resolve(value.trim()));
+                    template.headers.insert(name, value);
+                }
+            }
+            HeaderValue::List(headers) => {
+                for header in headers.iter().filter(|header| header.is_enabled()) {
+                    if let Some(key) = &header.key {
+                        let (name, value) = (self.resolve(key), self.resolve(&header.value()));
+                        template.headers.insert(name, value);
+                    }
+                }
+            }
+        }
+        if let Some(body) = &request.body
+            && !body.disabled
+        {
+            self.import_body(name, body, &mut template);
+        }
+        if let Some(auth) = request.auth.as_ref().or(auth) {
+            self.import_auth(name, auth, &mut template, &mut url);
+        }
+        template.url = Some(url);
+        template
+    }
+
+    fn import_url(&mut self, url: &PostmanUrl) -> String {
+        if let Some(raw) = &url.raw {
+            return self.resolve(raw);
+        }
+        let mut raw = String::new();
+        if let Some(protocol) = &url.protocol {
+            raw.push_str(&format!("{protocol}://"));
+        }
+        if let Some(host) = &url.host {
+            raw.push_str(&host.join("."));
+        }
+        if let Some(port) = &url.port {
+            raw.push_str(&format!(":{port}"));
+        }
+        if let Some(path) = &url.path {
+            raw.push('/');
+            raw.push_str(&path.join("/"));
+        }
+        let query: Vec<_> = url
+            .query
+            .iter()
+            .filter(|param| param.is_enabled())
+            .filter_map(|param| Some(format!("{}={}", param.key.as_ref()?, param.value())))
+            .collect();
+        if !query.is_empty() {
+            raw.push('?');
+            raw.push_str(&query.join("&"));
+        }
+        self.resolve(&raw)
+    }
+
+    fn import_body(&mut self, name: &str, body: &RequestBody, template: &mut RequestTemplate) {
+        let (content_type, body) = match body.mode.as_deref() {
+            Some("raw") => {
+                let language = body
+                    .options
+                    .as_ref()
+                    .and_then(|options| options.pointer("/raw/language"))
+                    .and_then(Value::as_str);
+                let content_type = match language {
+                    Some("json") => Some("application/json"),
+                    Some("xml") => Some("application/xml"),
+                    Some("html") => Some("text/html"),
+                    Some("javascript") => Some("application/javascript"),
+                    Some("text") => Some("text/plain"),
+                    _ => None,
+                };
+                (
+                    content_type,
+                    self.resolve(body.raw.as_deref().unwrap_or_default()),
+                )
+            }
+            Some("urlencoded") => {
+                let mut form = Url::parse("http://localhost").expect("Valid URL");
+                for param in body.urlencoded.iter().filter(|param| param.is_enabled()) {
+                    if let Some(key) = &param.key {
+                        let (key, value) = (self.resolve(key), self.resolve(&param.value()));
+                        form.query_pairs_mut().append_pair(&key, &value);
+                    }
+                }
+                let body = form.query().unwrap_or_default().to_owned();
+                (Some("application/x-www-form-urlencoded"), body)
+            }
+            Some("graphql") => {
+                let graphql = body.graphql.as_ref();
+                let query = graphql
+                    .and_then(|graphql| graphql.query.as_deref())
+                    .unwrap_or_default();
+                let variables = graphql
+                    .and_then(|graphql| graphql.variables.as_deref())
+                    .filter(|variables| !variables.trim().is_empty())
+                    .map(|variables| self.resolve(variables))
+                    .and_then(|variables| serde_json::from_str::<Value>(&variables).ok());
+                let body = json!({"query": self.resolve(query), "variables": variables});
+                (Some("application/json"), body.to_string())
+            }
+            Some(mode) => {
+                self.warnings.push(format!(
+                    "The '{mode}' body of '{name}' cannot be translated"
+                ));
+                return;
+            }
+            None => return,
+        };
+        if body.is_empty() {
+            return;
+        }
+        if let Some(content_type) = content_type
+            && !template
+                .headers
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case("content-type"))
+        {
+            template
+                .headers
+                .insert("Content-Type".to_owned(), content_type.to_owned());
+        }
+        template.body = Some(body);
+    }
+
+    fn import_auth(
+        &mut self,
+        name: &str,
+        auth: &Auth,
+        template: &mut RequestTemplate,
+        url: &mut String,
+    ) {
+        let attribute = |importer: &mut Importer, name: &str| {
+            let value = auth.attribute(name).unwrap_or_default();
+            importer.resolve(&value)
+        };
+        match auth.kind.as_str() {
+            "noauth" => {}
+            "bearer" => {
+                let token = attribute(self, "token");
+                template
+                    .headers
+                    .insert("Authorization".to_owned(), format!("Bearer {token}"));
+            }
+            "basic" => {
+                let username = attribute(self, "username");
+                let password = attribute(self, "password");
+                let credentials = BASE64_STANDARD.encode(format!("{username}:{password}"));
+                template
+                    .headers
+                    .insert("Authorization".to_owned(), format!("Basic {credentials}"));
+            }
+            "apikey" => {
+                let key = attribute(self, "key");
+                let value = attribute(self, "value");
+                if auth.attribute("in").as_deref() == Some("query") {
+                    let separator = if url.contains('?') { '&' } else { '?' };
+                    url.push_str(&format!("{separator}{key}={value}"));
+                } else {
+                    template.headers.insert(key, value);
+                }
+            }
+            kind => self.warnings.push(format!(
+                "The '{kind}' auth of '{name}' cannot be translated"
+            )),
+        }
+    }
+
+    /// Replaces the `{{name}}` variables of a value. Unknown variables are kept as is, so they can
+    /// be supplied by a feeder.
+    fn resolve(&mut self, value: &str) -> String {
+        let mut value = value.to_owned();
+        for _ in 0..MAX_RESOLVE_DEPTH {
+            let mut resolved = String::with_capacity(value.len());
+            let mut rest = value.as_str();
+            let mut changed = false;
+            while let Some(start) = rest.find("{{") {
+                let Some(end) = rest[start..].find("}}") else {
+                    break;
+                };
+                let name = rest[start + 2..start + end].trim();
+                resolved.push_str(&rest[..start]);
+                if let Some(value) = self.variables.get(name) {
+                    resolved.push_str(value);
+                    changed = true;
+                } else if let Some((_, expr)) = DYNAMIC_VARIABLES.iter().find(|(n, _)| *n == name) {
+                    resolved.push_str(&format!("{{{{{expr}}}}}"));
+                } else {
+                    self.unknown.insert(name.to_owned());
+                    resolved.push_str(&rest[start..start + end + 2]);
+                }
+                rest = &rest[start + end + 2..];
+            }
+            resolved.push_str(rest);
+            value = resolved;
+            if !changed {
+                break;
+            }
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLLECTION: &str = r#"{
+        "info": {
+            "name": "Shop",
+            "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+        },
+        "variable": [
+            {"key": "baseUrl", "value": "http://localhost:8080"},
+            {"key": "apiUrl", "value": "{{baseUrl}}/api"},
+            {"key": "token", "value": "collection-token"}
+        ],
+        "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "{{token}}", "type": "string"}]},
+        "item": [
+            {
+                "name": "Orders",
+                "event": [{"listen": "prerequest", "script": {"exec": ["pm.variables.set('x', 1);"]}}],
+                "item": [
+                    {
+                        "name": "Create order",
+                        "request": {
+                            "method": "POST",
+                            "header": [
+                                {"key": "X-Request-Id", "value": "{{$guid}}"},
+                                {"key": "X-Debug", "value": "1", "disabled": true}
+                            ],
+                            "body": {
+                                "mode": "raw",
+                                "raw": "{\"sku\": \"{{sku}}\", \"user\": \"{{userId}}\"}",
+                                "options": {"raw": {"language": "json"}}
+                            },
+                            "url": {
+                                "raw": "{{apiUrl}}/orders",
+                                "host": ["{{apiUrl}}"],
+                                "path": ["orders"]
+                            }
+                        }
+                    }
+                ]
+            },
+            {
+                "name": "Login",
+                "request": {
+                    "method": "post",
+                    "auth": {"type": "basic", "basic": [
+                        {"key": "username", "value": "alice"},
+                        {"key": "password", "value": "secret"}
+                    ]},
+                    "body": {
+                        "mode": "urlencoded",
+                        "urlencoded": [
+                            {"key": "remember", "value": "yes please"},
+                            {"key": "debug", "value": "1", "disabled": true}
+                        ]
+                    },
+                    "url": {"protocol": "http", "host": ["localhost"], "port": "8080", "path": ["login"]}
+                }
+            },
+            {
+                "name": "Upload",
+                "request": {
+                    "method": "PUT",
+                    "auth": {"type": "oauth2"},
+                    "body": {"mode": "formdata", "formdata": [{"key": "file", "type": "file", "src": "a.png"}]},
+                    "url": "{{baseUrl}}/upload"
+                }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn parse_succeeds() {
+        let environment = vec![KeyValue {
+            key: Some("sku".to_owned()),
+            value: Some(json!(42)),
+            disabled: false,
+            enabled: Some(true),
+        }];
+
+        let collection = PostmanCollection::parse(COLLECTION, environment).unwrap();
+
+        assert_eq!(collection.len(), 3);
+        assert_eq!(
+            collection.to_manifest_lines().unwrap(),
+            vec![
+                r#"{"name":"Orders / Create order","method":"POST","url":"http://localhost:8080/api/orders","headers":{"Authorization":"Bearer collection-token","Content-Type":"application/json","X-Request-Id":"{{uuid}}"},"body":"{\"sku\": \"42\", \"user\": \"{{userId}}\"}"}"#,
+                r#"{"name":"Login","method":"POST","url":"http://localhost:8080/login","headers":{"Authorization":"Basic YWxpY2U6c2VjcmV0","Content-Type":"application/x-www-form-urlencoded"},"body":"remember=yes+please"}"#,
+                r#"{"name":"Upload","method":"PUT","url":"http://localhost:8080/upload"}"#,
+            ]
+        );
+        assert_eq!(
+            collection.warnings(),
+            [
+                "The pre-request script of 'Orders' cannot be translated",
+                "The 'formdata' body of 'Upload' cannot be translated",
+                "The 'oauth2' auth of 'Upload' cannot be translated",
+                "Unknown variable '{{userId}}' is kept as a template variable",
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_environment_overrides_collection_variables() {
+        let environment = vec![KeyValue {
+            key: Some("baseUrl".to_owned()),
+            value: Some(json!("https://staging.example.com")),
+            disabled: false,
+            enabled: None,
+        }];
+
+        let collection = PostmanCollection::parse(COLLECTION, environment).unwrap();
+
+        assert!(
+            collection.to_manifest_lines().unwrap()[2]
+                .contains(r#""url":"https://staging.example.com/upload""#)
+        );
+    }
+
+    #[test]
+    fn parse_invalid_collection_fails() {
+        assert!(PostmanCollection::parse("[]", Vec::new()).is_err());
+        assert_eq!(
+            PostmanCollection::parse(
+                r#"{"info": {"schema": "https://schema.getpostman.com/json/collection/v1.0.0/collection.json"}}"#,
+                Vec::new()
+            )
+            .unwrap_err()
+            .to_string(),
+            "Unsupported collection schema 'https://schema.getpostman.com/json/collection/v1.0.0/collection.json'"
+        );
+    }
+}
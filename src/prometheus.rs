@@ -0,0 +1,213 @@
+use crate::metrics::{Recorder, Sample};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+/// Upper bounds (in seconds) of the latency histogram buckets.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Counters and latency histogram of a single `(name, endpoint, status)` label set.
+#[derive(Debug, Default, Clone)]
+struct Series {
+    count: u64,
+    sum: f64,
+    buckets: [u64; BUCKETS.len()],
+}
+
+/// Label values of a [`Series`]: `(name, endpoint, status)`.
+type Labels = (String, String, String);
+
+/// A [`Recorder`] that exposes live metrics in the Prometheus text exposition format.
+#[derive(Debug, Default)]
+pub struct PrometheusExporter {
+    series: Mutex<BTreeMap<Labels, Series>>,
+}
+
+impl PrometheusExporter {
+    /// Creates a new `PrometheusExporter` with no recorded metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts serving the metrics on `/metrics` at the specified address in the background.
+    ///
+    /// # Parameters
+    ///
+    /// * `addr`: Address to listen on.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the address the server is bound to.
+    pub async fn serve(self: Arc<Self>, addr: SocketAddr) -> Result<SocketAddr> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let exporter = self.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    let mut request_line = String::new();
+                    if stream.read_line(&mut request_line).await.is_err() {
+                        return;
+                    }
+                    let response = match request_line.split_whitespace().nth(1) {
+                        Some("/metrics") => {
+                            let body = exporter.render();
+                            format!(
+                                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                                body.len()
+                            )
+                        }
+                        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_owned(),
+                    };
+                    let _ = stream.get_mut().write_all(response.as_bytes()).await;
+                    let _ = stream.get_mut().shutdown().await;
+                });
+            }
+        });
+        Ok(local_addr)
+    }
+
+    /// Renders the recorded metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let series = self.series.lock().unwrap();
+        let mut output = String::new();
+        output.push_str("# HELP load_rs_requests_total Total number of completed requests.\n");
+        output.push_str("# TYPE load_rs_requests_total counter\n");
+        for ((name, endpoint, status), s) in series.iter() {
+            let labels = Self::labels(name, endpoint, status);
+            let _ = writeln!(output, "load_rs_requests_total{{{labels}}} {}", s.count);
+        }
+        output.push_str("# HELP load_rs_request_duration_seconds Request latency in seconds.\n");
+        output.push_str("# TYPE load_rs_request_duration_seconds histogram\n");
+        for ((name, endpoint, status), s) in series.iter() {
+            let labels = Self::labels(name, endpoint, status);
+            for (bound, count) in BUCKETS.iter().zip(s.buckets.iter()) {
+                let _ = writeln!(
+                    output,
+                    "load_rs_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {count}"
+                );
+            }
+            let _ = writeln!(
+                output,
+                "load_rs_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                s.count
+            );
+            let _ = writeln!(
+                output,
+                "load_rs_request_duration_seconds_sum{{{labels}}} {}",
+                s.sum
+            );
+            let _ = writeln!(
+                output,
+                "load_rs_request_duration_seconds_count{{{labels}}} {}",
+                s.count
+            );
+        }
+        output
+    }
+
+    fn labels(name: &str, endpoint: &str, status: &str) -> String {
+        format!(
+            "name=\"{}\",endpoint=\"{}\",status=\"{}\"",
+            Self::escape(name),
+            Self::escape(endpoint),
+            Self::escape(status)
+        )
+    }
+
+    fn escape(value: &str) -> String {
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    }
+}
+
+impl Recorder for PrometheusExporter {
+    fn record(&self, sample: &Sample) {
+        let seconds = sample.duration.as_secs_f64();
+        let mut series = self.series.lock().unwrap();
+        let s = series
+            .entry((
+                sample.name.clone(),
+                sample.endpoint.clone(),
+                sample.status_label(),
+            ))
+            .or_default();
+        s.count += 1;
+        s.sum += seconds;
+        for (bound, count) in BUCKETS.iter().zip(s.buckets.iter_mut()) {
+            if seconds <= *bound {
+                *count += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    fn sample(endpoint: &str, status: Option<u16>, millis: u64) -> Sample {
+        Sample {
+            name: format!("GET {endpoint}"),
+            endpoint: endpoint.to_owned(),
+            status,
+            success: status == Some(200),
+            duration: Duration::from_millis(millis),
+            timestamp: SystemTime::now(),
+            trace: None,
+        }
+    }
+
+    #[test]
+    fn render_succeeds() {
+        let exporter = PrometheusExporter::new();
+        exporter.record(&sample("/get", Some(200), 20));
+        exporter.record(&sample("/get", Some(200), 200));
+        exporter.record(&sample("/get", None, 3));
+
+        let output = exporter.render();
+
+        assert!(output.contains(
+            "load_rs_requests_total{name=\"GET /get\",endpoint=\"/get\",status=\"200\"} 2"
+        ));
+        assert!(output.contains(
+            "load_rs_requests_total{name=\"GET /get\",endpoint=\"/get\",status=\"error\"} 1"
+        ));
+        assert!(output.contains(
+            "load_rs_request_duration_seconds_bucket{name=\"GET /get\",endpoint=\"/get\",status=\"200\",le=\"0.01\"} 0"
+        ));
+        assert!(output.contains(
+            "load_rs_request_duration_seconds_bucket{name=\"GET /get\",endpoint=\"/get\",status=\"200\",le=\"0.025\"} 1"
+        ));
+        assert!(output.contains(
+            "load_rs_request_duration_seconds_bucket{name=\"GET /get\",endpoint=\"/get\",status=\"200\",le=\"+Inf\"} 2"
+        ));
+        assert!(output.contains(
+            "load_rs_request_duration_seconds_count{name=\"GET /get\",endpoint=\"/get\",status=\"200\"} 2"
+        ));
+    }
+
+    #[test]
+    fn render_escapes_labels() {
+        let exporter = PrometheusExporter::new();
+        exporter.record(&sample("/a\"b\\c", Some(200), 1));
+
+        let output = exporter.render();
+
+        assert!(
+            output.contains("load_rs_requests_total{name=\"GET /a\\\"b\\\\c\",endpoint=\"/a\\\"b\\\\c\",status=\"200\"} 1")
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use crate::RequestTemplate;
+use crate::think_time::ThinkTime;
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::str::FromStr;
+use tokio::fs;
+
+/// JSON representation of a step of a scenario file.
+#[derive(Debug, Clone, Deserialize)]
+struct StepTemplate {
+    #[serde(flatten)]
+    request: RequestTemplate,
+    #[serde(default)]
+    extract: BTreeMap<String, ExtractorTemplate>,
+    think_time: Option<String>,
+}
+
+/// JSON representation of an extractor.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ExtractorTemplate {
+    Json(String),
+    Regex(String),
+    Header(String),
+}
+
+/// Extracts a value from a response.
+#[derive(Debug, Clone)]
+pub enum Extractor {
+    /// Extracts a value from a JSON body with a JSONPath expression, e.g. `$.data.items[0].id`.
+    Json(Vec<JsonPathSegment>),
+
+    /// Extracts the first capture group, or the whole match if there is no capture group, of a
+    /// regular expression from the body.
+    Regex(Regex),
+
+    /// Extracts the value of a header.
+    Header(HeaderName),
+}
+
+/// A segment of a JSONPath expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonPathSegment {
+    /// A member of an object.
+    Key(String),
+
+    /// An element of an array.
+    Index(usize),
+}
+
+impl Extractor {
+    fn parse(template: &ExtractorTemplate) -> Result<Self> {
+        match template {
+            ExtractorTemplate::Json(path) => Ok(Extractor::Json(Self::parse_json_path(path)?)),
+            ExtractorTemplate::Regex(regex) => Ok(Extractor::Regex(
+                Regex::new(regex).context(format!("Invalid regex '{regex}'"))?,
+            )),
+            ExtractorTemplate::Header(name) => Ok(Extractor::Header(
+                HeaderName::from_str(name).context(format!("Invalid header name '{name}'"))?,
+            )),
+        }
+    }
+
+    /// Parses a JSONPath expression that consists of `.key`, `['key']` and `[index]` segments.
+    fn parse_json_path(path: &str) -> Result<Vec<JsonPathSegment>> {
+        let Some(mut rest) = path.strip_prefix('$') else {
+            bail!("Invalid JSONPath '{path}': must start with '$'");
+        };
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(r) = rest.strip_prefix('.') {
+                let end = r.find(['.', '[']).unwrap_or(r.len());
+                if end == 0 {
+                    bail!("Invalid JSONPath '{path}': empty key");
+                }
+                segments.push(JsonPathSegment::Key(r[..end].to_owned()));
+                rest = &r[end..];
+            } else if let Some(r) = rest.strip_prefix('[') {
+                let end = r
+                    .find(']')
+                    .context(format!("Invalid JSONPath '{path}': unclosed '['"))?;
+                let segment = &r[..end];
+                if let Some(key) = segment
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                {
+                    segments.push(JsonPathSegment::Key(key.to_owned()));
+                } else {
+                    let index = segment.parse().context(format!(
+                        "Invalid JSONPath '{path}': invalid index '{segment}'"
+                    ))?;
+                    segments.push(JsonPathSegment::Index(index));
+                }
+                rest = &r[end + 1..];
+            } else {
+                bail!("Invalid JSONPath '{path}': unexpected '{rest}'");
+            }
+        }
+        Ok(segments)
+    }
+
+    /// Extracts a value from a response.
+    ///
+    /// # Parameters
+    ///
+    /// * `headers`: Headers of the response.
+    /// * `body`: Body of the response.
+    ///
+    /// # Returns
+    ///
+    /// The extracted value, or `None` if the value was not found.
+    pub fn extract(&self, headers: &HeaderMap, body: &[u8]) -> Option<String> {
+        match self {
+            Extractor::Json(path) => {
+                let root: Value = serde_json::from_slice(body).ok()?;
+                let mut value = &root;
+                for segment in path {
+                    value = match segment {
+                        JsonPathSegment::Key(key) => value.get(key)?,
+                        JsonPathSegment::Index(index) => value.get(index)?,
+                    };
+                }
+                Some(match value {
+                    Value::String(s) => s.clone(),
+                    value => value.to_string(),
+                })
+            }
+            Extractor::Regex(regex) => {
+                let body = str::from_utf8(body).ok()?;
+                let captures = regex.captures(body)?;
+                let value = captures.get(1).or_else(|| captures.get(0))?;
+                Some(value.as_str().to_owned())
+            }
+            Extractor::Header(name) => headers.get(name)?.to_str().ok().map(str::to_owned),
+        }
+    }
+}
+
+/// A step of a [`Scenario`].
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub(crate) request: RequestTemplate,
+
+    /// Variables extracted from the response, which are available to the later steps.
+    pub extract: Vec<(String, Extractor)>,
+
+    /// Pause after the step, which overrides the think time of the runner.
+    pub think_time: Option<ThinkTime>,
+}
+
+/// An ordered list of requests that is run for every iteration, where values extracted from a
+/// response become variables of the later requests.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    steps: Vec<Step>,
+}
+
+impl Scenario {
+    /// Reads a scenario file.
+    ///
+    /// The scenario file is a JSON Lines file where every line is a step with the same fields as a
+    /// manifest entry, plus an optional `extract` map of variable names to extractors, e.g.
+    /// `{"token": {"json": "$.token"}}`, and an optional `think_time`, e.g. `"1s..3s"`.
+    ///
+    /// # Parameters
+    ///
+    /// * `path`: Scenario file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `Scenario` instance if successful.
+    pub async fn from_file(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            bail!(
+                "Scenario file '{}' does not exist or is not a file",
+                path.display()
+            );
+        }
+        let content = fs::read_to_string(path).await?;
+        Self::parse(&content).context(format!("Invalid scenario file '{}'", path.display()))
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let mut steps = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let template: StepTemplate =
+                serde_json::from_str(line).context(format!("Invalid step on line {}", i + 1))?;
+            let extract = template
+                .extract
+                .iter()
+                .map(|(name, extractor)| Ok((name.clone(), Extractor::parse(extractor)?)))
+                .collect::<Result<_>>()
+                .context(format!("Invalid step on line {}", i + 1))?;
+            let think_time = template
+                .think_time
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .context(format!("Invalid step on line {}", i + 1))?;
+            steps.push(Step {
+                request: template.request,
+                extract,
+                think_time,
+            });
+        }
+        if steps.is_empty() {
+            bail!("Scenario does not contain any steps");
+        }
+        Ok(Scenario { steps })
+    }
+
+    /// Returns the steps of the scenario.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Returns the number of steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns `true` if there are no steps.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Returns the names of the variables that are extracted by the steps before the specified
+    /// step.
+    pub(crate) fn variables_before(&self, step: usize) -> BTreeSet<String> {
+        self.steps[..step]
+            .iter()
+            .flat_map(|step| step.extract.iter().map(|(name, _)| name.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_json_path_succeeds() {
+        assert_eq!(
+            Extractor::parse_json_path("$.data['user id'].items[2]").unwrap(),
+            vec![
+                JsonPathSegment::Key("data".to_owned()),
+                JsonPathSegment::Key("user id".to_owned()),
+                JsonPathSegment::Key("items".to_owned()),
+                JsonPathSegment::Index(2),
+            ]
+        );
+        assert!(Extractor::parse_json_path("$").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_invalid_json_path_fails() {
+        assert_eq!(
+            Extractor::parse_json_path("data.id")
+                .unwrap_err()
+                .to_string(),
+            "Invalid JSONPath 'data.id': must start with '$'"
+        );
+        assert_eq!(
+            Extractor::parse_json_path("$.items[x]")
+                .unwrap_err()
+                .to_string(),
+            "Invalid JSONPath '$.items[x]': invalid index 'x'"
+        );
+    }
+
+    #[test]
+    fn extract_succeeds() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-session", "abc".parse().unwrap());
+        let body = br#"{"token": "t0k3n", "order": {"items": [{"id": 7}]}}"#;
+
+        let json = Extractor::parse(&ExtractorTemplate::Json("$.token".to_owned())).unwrap();
+        let nested =
+            Extractor::parse(&ExtractorTemplate::Json("$.order.items[0].id".to_owned())).unwrap();
+        let regex =
+            Extractor::parse(&ExtractorTemplate::Regex("\"id\": (\\d+)".to_owned())).unwrap();
+        let header = Extractor::parse(&ExtractorTemplate::Header("X-Session".to_owned())).unwrap();
+        let missing = Extractor::parse(&ExtractorTemplate::Json("$.missing".to_owned())).unwrap();
+
+        assert_eq!(json.extract(&headers, body).unwrap(), "t0k3n");
+        assert_eq!(nested.extract(&headers, body).unwrap(), "7");
+        assert_eq!(regex.extract(&headers, body).unwrap(), "7");
+        assert_eq!(header.extract(&headers, body).unwrap(), "abc");
+        assert!(missing.extract(&headers, body).is_none());
+    }
+
+    #[test]
+    fn parse_scenario_succeeds() {
+        let scenario = Scenario::parse(
+            r#"{"method": "POST", "path": "/login", "extract": {"token": {"json": "$.token"}}}
+
+{"method": "GET", "path": "/orders", "headers": {"authorization": "Bearer {{token}}"}, "think_time": "2s"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(scenario.len(), 2);
+        assert!(scenario.steps()[0].think_time.is_none());
+        assert_eq!(
+            scenario.steps()[1].think_time,
+            Some(ThinkTime::Fixed(std::time::Duration::from_secs(2)))
+        );
+        assert!(scenario.variables_before(0).is_empty());
+        assert_eq!(
+            scenario.variables_before(1).into_iter().collect::<Vec<_>>(),
+            vec!["token"]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_scenario_fails() {
+        let err = Scenario::parse(r#"{"extract": {"id": {"regex": "("}}}"#).unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid step on line 1");
+        assert!(Scenario::parse("\n").is_err());
+        assert!(Scenario::parse(r#"{"think_time": "2"}"#).is_err());
+    }
+}
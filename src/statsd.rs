@@ -0,0 +1,337 @@
+use crate::metrics::{Recorder, Sample};
+use anyhow::{Context, Result, bail};
+use futures::future::BoxFuture;
+use std::fmt::Write;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::net::lookup_host;
+
+/// Maximum size of a single UDP packet. This keeps packets below the common Ethernet MTU.
+const MAX_PACKET_SIZE: usize = 1432;
+
+/// Interval at which buffered metrics are flushed even if the packet is not full.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Parses a tag in "key:value" format to add to every metric.
+pub fn parse_tag(s: &str) -> Result<String> {
+    match s.split_once(':') {
+        Some((key, value))
+            if !key.is_empty()
+                && !value.is_empty()
+                && !s.contains([' ', ',', '|', '#', '\r', '\n']) =>
+        {
+            Ok(s.to_owned())
+        }
+        _ => bail!("'{s}' is not a valid StatsD tag, expected KEY:VALUE"),
+    }
+}
+
+/// A [`Recorder`] that sends per-request timings and counters to a StatsD server over UDP.
+///
+/// Tags are sent using the DogStatsD format (`|#key:value,...`). Metrics are buffered and sent in
+/// batches of up to [`MAX_PACKET_SIZE`] bytes, so that a high request rate does not result in one
+/// UDP packet per metric. Metrics of packets that cannot be sent are dropped and counted by
+/// [`Recorder::dropped`].
+#[derive(Debug)]
+pub struct StatsdClient {
+    socket: UdpSocket,
+    prefix: String,
+    tags: Vec<String>,
+    buffer: Mutex<String>,
+    dropped: AtomicU64,
+}
+
+impl StatsdClient {
+    /// Creates a new `StatsdClient` and starts flushing buffered metrics periodically in the
+    /// background.
+    ///
+    /// # Parameters
+    ///
+    /// * `addr`: StatsD server address in "host:port" format.
+    /// * `prefix`: Prefix of every metric name.
+    /// * `tags`: Tags in "key:value" format to add to every metric.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `StatsdClient` instance if successful, or an error if the
+    /// address cannot be resolved or a tag is not in "key:value" format.
+    pub async fn new(addr: &str, prefix: &str, tags: &[String]) -> Result<Arc<Self>> {
+        for tag in tags {
+            parse_tag(tag)?;
+        }
+        let server = lookup_host(addr)
+            .await
+            .context(format!("Unable to resolve StatsD server '{addr}'"))?
+            .next()
+            .context(format!("Unable to resolve StatsD server '{addr}'"))?;
+        // The socket is bound to the address family of the server, so that IPv6 servers work too.
+        let local: SocketAddr = match server {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = tokio::net::UdpSocket::bind(local).await?;
+        socket.connect(server).await?;
+        // Metrics are sent without awaiting, so the non-blocking socket is used directly.
+        let socket = socket.into_std()?;
+        let client = Arc::new(StatsdClient {
+            socket,
+            prefix: prefix.to_owned(),
+            tags: tags.to_vec(),
+            buffer: Mutex::new(String::new()),
+            dropped: AtomicU64::new(0),
+        });
+        let weak = Arc::downgrade(&client);
+        tokio::spawn(Self::flush_periodically(weak));
+        Ok(client)
+    }
+
+    async fn flush_periodically(client: Weak<Self>) {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            match client.upgrade() {
+                Some(client) => client.flush(),
+                None => break,
+            }
+        }
+    }
+
+    fn push(&self, line: &str) {
+        let mut buffer = self.buffer.lock().unwrap();
+        if !buffer.is_empty() && buffer.len() + 1 + line.len() > MAX_PACKET_SIZE {
+            self.send(&buffer);
+            buffer.clear();
+        }
+        if !buffer.is_empty() {
+            buffer.push('\n');
+        }
+        buffer.push_str(line);
+    }
+
+    fn flush(&self) {
+        let mut buffer = self.buffer.lock().unwrap();
+        if !buffer.is_empty() {
+            self.send(&buffer);
+            buffer.clear();
+        }
+    }
+
+    fn send(&self, packet: &str) {
+        // StatsD is fire-and-forget, so a packet that cannot be sent right away is dropped.
+        if self.socket.send(packet.as_bytes()).is_err() {
+            self.dropped
+                .fetch_add(packet.lines().count() as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn format_tags(&self, sample: &Sample) -> String {
+        let mut tags = format!(
+            "name:{},endpoint:{},status:{}",
+            Self::sanitize(&sample.name),
+            Self::sanitize(&sample.endpoint),
+            sample.status_label()
+        );
+        for tag in &self.tags {
+            let _ = write!(tags, ",{tag}");
+        }
+        tags
+    }
+
+    /// Replaces the characters that separate DogStatsD metrics, fields and tags in a tag value.
+    fn sanitize(value: &str) -> String {
+        value.replace([' ', ',', '|', ':', '#', '\n'], "_")
+    }
+}
+
+impl Recorder for StatsdClient {
+    fn record(&self, sample: &Sample) {
+        let tags = self.format_tags(sample);
+        let outcome = if sample.success { "success" } else { "failure" };
+        self.push(&format!("{}.requests:1|c|#{tags}", self.prefix));
+        self.push(&format!("{}.{outcome}:1|c|#{tags}", self.prefix));
+        self.push(&format!(
+            "{}.request_duration:{}|ms|#{tags}",
+            self.prefix,
+            sample.duration.as_secs_f64() * 1000.0
+        ));
+    }
+
+    fn finish(&self) -> BoxFuture<'_, Result<()>> {
+        self.flush();
+        Box::pin(async { Ok(()) })
+    }
+
+    fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+    use tokio::net::UdpSocket;
+    use tokio::time::timeout;
+
+    async fn recv(server: &UdpSocket, buf: &mut [u8]) -> usize {
+        timeout(Duration::from_secs(5), server.recv(buf))
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn record_sends_batched_metrics() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = StatsdClient::new(
+            &server.local_addr().unwrap().to_string(),
+            "load_rs",
+            &["env:test".to_owned()],
+        )
+        .await
+        .unwrap();
+        let sample = Sample {
+            name: "GET /get".to_owned(),
+            endpoint: "/get".to_owned(),
+            status: Some(200),
+            success: true,
+            duration: Duration::from_millis(12),
+            timestamp: SystemTime::now(),
+            trace: None,
+        };
+        client.record(&sample);
+        client.record(&sample);
+        client.finish().await.unwrap();
+
+        let mut buf = [0; MAX_PACKET_SIZE];
+        let len = recv(&server, &mut buf).await;
+        let packet = str::from_utf8(&buf[..len]).unwrap();
+        let lines: Vec<&str> = packet.lines().collect();
+
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[0],
+            "load_rs.requests:1|c|#name:GET_/get,endpoint:/get,status:200,env:test"
+        );
+        assert_eq!(
+            lines[1],
+            "load_rs.success:1|c|#name:GET_/get,endpoint:/get,status:200,env:test"
+        );
+        assert_eq!(
+            lines[2],
+            "load_rs.request_duration:12|ms|#name:GET_/get,endpoint:/get,status:200,env:test"
+        );
+    }
+
+    #[test]
+    fn format_tags_sanitizes_values() {
+        let client = StatsdClient {
+            socket: std::net::UdpSocket::bind("127.0.0.1:0").unwrap(),
+            prefix: "load_rs".to_owned(),
+            tags: Vec::new(),
+            buffer: Mutex::new(String::new()),
+            dropped: AtomicU64::new(0),
+        };
+        let sample = Sample {
+            name: "a,b|c:d#e f".to_owned(),
+            endpoint: "/items/1:2".to_owned(),
+            status: None,
+            success: false,
+            duration: Duration::from_millis(1),
+            timestamp: SystemTime::now(),
+            trace: None,
+        };
+
+        assert_eq!(
+            client.format_tags(&sample),
+            "name:a_b_c_d_e_f,endpoint:/items/1_2,status:error"
+        );
+    }
+
+    #[tokio::test]
+    async fn send_counts_dropped_metrics() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = StatsdClient::new(&server.local_addr().unwrap().to_string(), "load_rs", &[])
+            .await
+            .unwrap();
+
+        // A packet larger than the maximum UDP datagram size cannot be sent.
+        let line = "x".repeat(40000);
+        client.send(&format!("{line}\n{line}"));
+
+        assert_eq!(client.dropped(), 2);
+    }
+
+    #[tokio::test]
+    async fn record_splits_packets() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = StatsdClient::new(&server.local_addr().unwrap().to_string(), "load_rs", &[])
+            .await
+            .unwrap();
+        let sample = Sample {
+            name: "GET /get".to_owned(),
+            endpoint: "/get".to_owned(),
+            status: None,
+            success: false,
+            duration: Duration::from_millis(1),
+            timestamp: SystemTime::now(),
+            trace: None,
+        };
+        for _ in 0..100 {
+            client.record(&sample);
+        }
+        client.finish().await.unwrap();
+
+        let mut buf = [0; 65536];
+        let mut lines = 0;
+        while lines < 300 {
+            let len = recv(&server, &mut buf).await;
+            assert!(len <= MAX_PACKET_SIZE);
+            lines += str::from_utf8(&buf[..len]).unwrap().lines().count();
+        }
+
+        assert_eq!(lines, 300);
+    }
+
+    #[tokio::test]
+    async fn new_binds_ipv6_socket() {
+        let Ok(server) = UdpSocket::bind("[::1]:0").await else {
+            // IPv6 is not available.
+            return;
+        };
+        let client = StatsdClient::new(&server.local_addr().unwrap().to_string(), "load_rs", &[])
+            .await
+            .unwrap();
+        client.send("load_rs.requests:1|c");
+
+        let mut buf = [0; MAX_PACKET_SIZE];
+        let len = recv(&server, &mut buf).await;
+
+        assert_eq!(&buf[..len], b"load_rs.requests:1|c");
+        assert_eq!(client.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn new_fails_with_invalid_tag() {
+        let result = StatsdClient::new("127.0.0.1:8125", "load_rs", &["env".to_owned()]).await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "'env' is not a valid StatsD tag, expected KEY:VALUE"
+        );
+    }
+
+    #[test]
+    fn parse_tag_succeeds() {
+        assert_eq!(parse_tag("env:test").unwrap(), "env:test");
+        assert_eq!(parse_tag("url:http://x").unwrap(), "url:http://x");
+        assert!(parse_tag("env").is_err());
+        assert!(parse_tag(":test").is_err());
+        assert!(parse_tag("env:").is_err());
+        assert!(parse_tag("env:a,b").is_err());
+        assert!(parse_tag("env:a|b").is_err());
+        assert!(parse_tag("env:a b").is_err());
+    }
+}
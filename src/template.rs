@@ -0,0 +1,324 @@
+use crate::feeder::Row;
+use anyhow::{Context, Result, bail};
+use chrono::{SecondsFormat, Utc};
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use uuid::Uuid;
+
+/// Per-request values available when rendering a [`Template`].
+#[derive(Debug, Clone, Default)]
+pub struct RenderContext {
+    /// Sequence number of the request, starting at 1.
+    pub seq: u64,
+
+    /// Variables of the feeder row picked for the request.
+    pub variables: Option<Row>,
+}
+
+/// A template expression between `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    /// `{{uuid}}`: A random UUID.
+    Uuid,
+
+    /// `{{seq}}`: The sequence number of the request.
+    Seq,
+
+    /// `{{random_int MIN MAX}}`: A random integer between `MIN` and `MAX` (inclusive).
+    RandomInt(i64, i64),
+
+    /// `{{now_rfc3339}}`: The current time in RFC 3339 format.
+    NowRfc3339,
+
+    /// `{{random_string LEN}}`: A random alphanumeric string of length `LEN`.
+    RandomString(usize),
+
+    /// `{{NAME}}`: The value of the `NAME` variable.
+    Var(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Expr(Expr),
+}
+
+/// A string with `{{...}}` expressions that is parsed once and rendered for every request.
+///
+/// The following expressions are supported:
+///
+/// * `{{uuid}}`: A random UUID.
+/// * `{{seq}}`: The sequence number of the request, starting at 1.
+/// * `{{random_int MIN MAX}}`: A random integer between `MIN` and `MAX` (inclusive).
+/// * `{{now_rfc3339}}`: The current time in RFC 3339 format.
+/// * `{{random_string LEN}}`: A random alphanumeric string of length `LEN`.
+/// * `{{env "NAME"}}`: The value of the `NAME` environment variable, resolved when parsing.
+/// * `{{NAME}}`: The value of the `NAME` variable, e.g. a column of a feeder row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Creates a template that renders the specified string as is.
+    pub fn literal(s: &str) -> Self {
+        Template {
+            parts: vec![Part::Literal(s.to_owned())],
+        }
+    }
+
+    /// Parses a template.
+    ///
+    /// # Parameters
+    ///
+    /// * `s`: A string with `{{...}}` expressions.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed `Template` if successful.
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = s;
+        while let Some(start) = rest.find("{{") {
+            literal.push_str(&rest[..start]);
+            let end = rest[start..]
+                .find("}}")
+                .context(format!("Unclosed template expression in '{s}'"))?;
+            let expr = rest[start + 2..start + end].trim();
+            match Self::parse_expr(expr)? {
+                Part::Literal(value) => literal.push_str(&value),
+                part => {
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(part);
+                }
+            }
+            rest = &rest[start + end + 2..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Parses an expression. Expressions that can be resolved when parsing, such as `env`, are
+    /// returned as a literal.
+    fn parse_expr(expr: &str) -> Result<Part> {
+        let mut tokens = expr.split_whitespace();
+        let name = tokens.next().unwrap_or_default();
+        let args: Vec<&str> = tokens.collect();
+        let expected_args = match name {
+            "uuid" | "seq" | "now_rfc3339" => 0,
+            "random_string" | "env" => 1,
+            "random_int" => 2,
+            _ if args.is_empty() && Self::is_identifier(name) => {
+                return Ok(Part::Expr(Expr::Var(name.to_owned())));
+            }
+            _ => bail!("Unknown template function '{name}'"),
+        };
+        if args.len() != expected_args {
+            bail!(
+                "Template function '{name}' expects {expected_args} argument(s) but got {}",
+                args.len()
+            );
+        }
+        let expr = match name {
+            "uuid" => Expr::Uuid,
+            "seq" => Expr::Seq,
+            "now_rfc3339" => Expr::NowRfc3339,
+            "random_int" => {
+                let min: i64 = args[0]
+                    .parse()
+                    .context(format!("Invalid 'random_int' minimum: {}", args[0]))?;
+                let max: i64 = args[1]
+                    .parse()
+                    .context(format!("Invalid 'random_int' maximum: {}", args[1]))?;
+                if min > max {
+                    bail!("Invalid 'random_int' range: {min} is greater than {max}");
+                }
+                Expr::RandomInt(min, max)
+            }
+            "random_string" => Expr::RandomString(
+                args[0]
+                    .parse()
+                    .context(format!("Invalid 'random_string' length: {}", args[0]))?,
+            ),
+            _ => {
+                let var = args[0].trim_matches('"');
+                return Ok(Part::Literal(
+                    std::env::var(var)
+                        .context(format!("Environment variable '{var}' is not set"))?,
+                ));
+            }
+        };
+        Ok(Part::Expr(expr))
+    }
+
+    fn is_identifier(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    }
+
+    /// Returns the names of all variables used in the template.
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Expr(Expr::Var(name)) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns `true` if the template renders the same string for every request.
+    pub fn is_static(&self) -> bool {
+        self.parts
+            .iter()
+            .all(|part| matches!(part, Part::Literal(_)))
+    }
+
+    /// Renders the template.
+    ///
+    /// # Parameters
+    ///
+    /// * `context`: Per-request values.
+    ///
+    /// # Returns
+    ///
+    /// The rendered string.
+    pub fn render(&self, context: &RenderContext) -> String {
+        let mut output = String::new();
+        let mut random = rand::rng();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => output.push_str(s),
+                Part::Expr(Expr::Uuid) => output.push_str(&Uuid::new_v4().to_string()),
+                Part::Expr(Expr::Seq) => output.push_str(&context.seq.to_string()),
+                Part::Expr(Expr::RandomInt(min, max)) => {
+                    output.push_str(&random.random_range(*min..=*max).to_string())
+                }
+                Part::Expr(Expr::NowRfc3339) => {
+                    output.push_str(&Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true))
+                }
+                Part::Expr(Expr::RandomString(len)) => output.extend(
+                    (&mut random)
+                        .sample_iter(Alphanumeric)
+                        .take(*len)
+                        .map(char::from),
+                ),
+                Part::Expr(Expr::Var(name)) => {
+                    if let Some(value) = context.variables.as_ref().and_then(|v| v.get(name)) {
+                        output.push_str(value);
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_literal_succeeds() {
+        let template = Template::parse("hello world").unwrap();
+
+        assert!(template.is_static());
+        assert_eq!(template.render(&RenderContext::default()), "hello world");
+    }
+
+    #[test]
+    fn render_succeeds() {
+        let template =
+            Template::parse("/items/{{ seq }}?n={{random_int 5 5}}&s={{random_string 8}}").unwrap();
+
+        let output = template.render(&RenderContext {
+            seq: 42,
+            ..Default::default()
+        });
+
+        assert!(!template.is_static());
+        assert!(output.starts_with("/items/42?n=5&s="));
+        assert_eq!(output.len(), "/items/42?n=5&s=".len() + 8);
+    }
+
+    #[test]
+    fn render_uuid_and_now_succeeds() {
+        let template = Template::parse("{{uuid}} {{now_rfc3339}}").unwrap();
+
+        let output = template.render(&RenderContext::default());
+        let (uuid, now) = output.split_once(' ').unwrap();
+
+        assert!(Uuid::parse_str(uuid).is_ok());
+        assert!(chrono::DateTime::parse_from_rfc3339(now).is_ok());
+        assert_ne!(
+            template.render(&RenderContext::default()),
+            template.render(&RenderContext::default())
+        );
+    }
+
+    #[test]
+    fn parse_env_succeeds() {
+        let template = Template::parse("Bearer {{env \"PATH\"}}").unwrap();
+
+        assert!(template.is_static());
+        assert_eq!(
+            template.render(&RenderContext::default()),
+            format!("Bearer {}", std::env::var("PATH").unwrap())
+        );
+    }
+
+    #[test]
+    fn render_variables_succeeds() {
+        let template = Template::parse("/users/{{user_id}}?sku={{sku}}").unwrap();
+        let variables = Row::new(
+            [("user_id", "42"), ("sku", "abc")]
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect(),
+        );
+
+        let output = template.render(&RenderContext {
+            seq: 1,
+            variables: Some(variables),
+        });
+
+        assert_eq!(
+            template.variables().collect::<Vec<_>>(),
+            vec!["user_id", "sku"]
+        );
+        assert_eq!(output, "/users/42?sku=abc");
+    }
+
+    #[test]
+    fn parse_invalid_template_fails() {
+        assert_eq!(
+            Template::parse("{{seq").unwrap_err().to_string(),
+            "Unclosed template expression in '{{seq'"
+        );
+        assert_eq!(
+            Template::parse("{{foo 1}}").unwrap_err().to_string(),
+            "Unknown template function 'foo'"
+        );
+        assert_eq!(
+            Template::parse("{{random_int 1}}").unwrap_err().to_string(),
+            "Template function 'random_int' expects 2 argument(s) but got 1"
+        );
+        assert_eq!(
+            Template::parse("{{random_int 10 1}}")
+                .unwrap_err()
+                .to_string(),
+            "Invalid 'random_int' range: 10 is greater than 1"
+        );
+        assert_eq!(
+            Template::parse("{{env \"LOAD_RS_DOES_NOT_EXIST\"}}")
+                .unwrap_err()
+                .to_string(),
+            "Environment variable 'LOAD_RS_DOES_NOT_EXIST' is not set"
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::{Context, Result, bail};
+use rand::Rng;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A pause between the requests of a virtual user, which does not count towards the latency
+/// statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThinkTime {
+    /// A fixed pause, e.g. `2s`.
+    Fixed(Duration),
+
+    /// A pause that is uniformly distributed between a minimum and a maximum, e.g. `1s..3s`.
+    Uniform(Duration, Duration),
+
+    /// A pause that is exponentially distributed with a mean, e.g. `exp:2s`.
+    Exponential(Duration),
+}
+
+impl ThinkTime {
+    /// Returns the duration of a single pause.
+    pub fn sample(&self) -> Duration {
+        match self {
+            ThinkTime::Fixed(duration) => *duration,
+            ThinkTime::Uniform(min, max) => rand::rng().random_range(*min..=*max),
+            ThinkTime::Exponential(mean) => {
+                let u: f64 = rand::rng().random();
+                mean.mul_f64(-(1.0 - u).ln())
+            }
+        }
+    }
+}
+
+impl FromStr for ThinkTime {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(mean) = s.strip_prefix("exp:") {
+            return Ok(ThinkTime::Exponential(parse_duration(mean)?));
+        }
+        if let Some((min, max)) = s.split_once("..") {
+            let min = parse_duration(min)?;
+            let max = parse_duration(max)?;
+            if min > max {
+                bail!("Invalid think time '{s}': minimum is greater than maximum");
+            }
+            return Ok(ThinkTime::Uniform(min, max));
+        }
+        Ok(ThinkTime::Fixed(parse_duration(s)?))
+    }
+}
+
+/// Parses a duration with a `ms`, `s` or `m` unit, e.g. `500ms`, `1.5s` or `2m`.
+///
+/// # Parameters
+///
+/// * `s`: A duration.
+///
+/// # Returns
+///
+/// A `Result` containing the parsed `Duration` if successful.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    // The unit is the trailing letters, so that exponents like `1e3s` are part of the value.
+    let (value, unit) = s.split_at(s.trim_end_matches(|c: char| c.is_ascii_alphabetic()).len());
+    let value: f64 = value.parse().context(format!("Invalid duration '{s}'"))?;
+    if !value.is_finite() || value < 0.0 {
+        bail!("Invalid duration '{s}'");
+    }
+    let secs = match unit {
+        "ms" => value / 1000.0,
+        "s" => value,
+        "m" => value * 60.0,
+        _ => bail!("Invalid duration '{s}': expected a 'ms', 's' or 'm' unit"),
+    };
+    Duration::try_from_secs_f64(secs).context(format!("Invalid duration '{s}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_succeeds() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn parse_invalid_duration_fails() {
+        assert_eq!(
+            parse_duration("2").unwrap_err().to_string(),
+            "Invalid duration '2': expected a 'ms', 's' or 'm' unit"
+        );
+        assert_eq!(
+            parse_duration("abc").unwrap_err().to_string(),
+            "Invalid duration 'abc'"
+        );
+        assert!(parse_duration("-1s").is_err());
+        assert_eq!(
+            parse_duration("1e20s").unwrap_err().to_string(),
+            "Invalid duration '1e20s'"
+        );
+        assert_eq!(
+            parse_duration("1e18m").unwrap_err().to_string(),
+            "Invalid duration '1e18m'"
+        );
+    }
+
+    #[test]
+    fn parse_think_time_succeeds() {
+        assert_eq!(
+            "2s".parse::<ThinkTime>().unwrap(),
+            ThinkTime::Fixed(Duration::from_secs(2))
+        );
+        assert_eq!(
+            "1s..3s".parse::<ThinkTime>().unwrap(),
+            ThinkTime::Uniform(Duration::from_secs(1), Duration::from_secs(3))
+        );
+        assert_eq!(
+            "exp:500ms".parse::<ThinkTime>().unwrap(),
+            ThinkTime::Exponential(Duration::from_millis(500))
+        );
+        assert_eq!(
+            "3s..1s".parse::<ThinkTime>().unwrap_err().to_string(),
+            "Invalid think time '3s..1s': minimum is greater than maximum"
+        );
+    }
+
+    #[test]
+    fn sample_succeeds() {
+        let uniform = ThinkTime::Uniform(Duration::from_millis(10), Duration::from_millis(20));
+        let exponential = ThinkTime::Exponential(Duration::from_millis(100));
+
+        for _ in 0..100 {
+            let duration = uniform.sample();
+            assert!(duration >= Duration::from_millis(10) && duration <= Duration::from_millis(20));
+        }
+        let total: Duration = (0..1000).map(|_| exponential.sample()).sum();
+        assert!(total > Duration::from_secs(50) && total < Duration::from_secs(200));
+    }
+}
@@ -0,0 +1,355 @@
+use crate::HttpMethod;
+use crate::metrics::{Recorder, Sample};
+use anyhow::{Result, bail};
+use futures::future::BoxFuture;
+use rand::Rng;
+use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+/// Maximum number of spans sent in a single export request.
+const MAX_BATCH_SIZE: usize = 512;
+
+/// Maximum number of buffered spans. Further spans are dropped until the buffer has been
+/// exported, so that memory does not grow if the collector is down or slow.
+const MAX_BUFFERED_SPANS: usize = 16 * MAX_BATCH_SIZE;
+
+/// Interval at which buffered spans are exported even if the batch is not full.
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Parses a ratio of requests between 0.0 and 1.0 to sample for tracing.
+pub fn parse_sample_ratio(s: &str) -> Result<f64> {
+    match s.parse::<f64>() {
+        Ok(ratio) => check_sample_ratio(ratio),
+        Err(_) => bail!("'{s}' is not a valid sample ratio"),
+    }
+}
+
+/// Checks that a sample ratio is between 0.0 and 1.0, which also rejects NaN.
+pub(crate) fn check_sample_ratio(ratio: f64) -> Result<f64> {
+    if !(0.0..=1.0).contains(&ratio) {
+        bail!("Sample ratio '{ratio}' must be between 0.0 and 1.0");
+    }
+    Ok(ratio)
+}
+
+/// W3C trace context of a single request.
+#[derive(Debug, Clone)]
+pub struct TraceContext {
+    /// 16-byte trace ID as a lowercase hex string.
+    pub trace_id: String,
+
+    /// 8-byte span ID of the client span as a lowercase hex string.
+    pub span_id: String,
+
+    /// Whether the span is sampled, i.e. exported.
+    pub sampled: bool,
+
+    /// HTTP method of the request.
+    pub method: HttpMethod,
+
+    /// Time at which the request was sent.
+    pub start_time: SystemTime,
+}
+
+impl TraceContext {
+    /// Creates a new `TraceContext` with a random trace ID and span ID.
+    ///
+    /// # Parameters
+    ///
+    /// * `method`: HTTP method of the request.
+    /// * `sampled`: Whether the span is sampled.
+    pub fn new(method: HttpMethod, sampled: bool) -> Self {
+        let mut random = rand::rng();
+        TraceContext {
+            trace_id: format!("{:032x}", random.random_range(1..=u128::MAX)),
+            span_id: format!("{:016x}", random.random_range(1..=u64::MAX)),
+            sampled,
+            method,
+            start_time: SystemTime::now(),
+        }
+    }
+
+    /// Returns the value of the `traceparent` header.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{}",
+            self.trace_id,
+            self.span_id,
+            if self.sampled { "01" } else { "00" }
+        )
+    }
+}
+
+/// A [`Recorder`] that exports a client span for every sampled request to an OpenTelemetry
+/// collector using OTLP/HTTP with JSON encoding.
+///
+/// Spans are buffered and exported in batches of up to [`MAX_BATCH_SIZE`] spans. Spans that do not
+/// fit into the buffer or that fail to export are dropped and counted by [`Recorder::dropped`].
+#[derive(Debug)]
+pub struct OtlpExporter {
+    endpoint: String,
+    service_name: String,
+    spans: Mutex<Vec<Value>>,
+    batch_full: Notify,
+    dropped: AtomicU64,
+    client: Client,
+}
+
+impl OtlpExporter {
+    /// Creates a new `OtlpExporter` and starts exporting buffered spans periodically in the
+    /// background.
+    ///
+    /// # Parameters
+    ///
+    /// * `endpoint`: Base URL of the OTLP/HTTP collector, e.g. `http://localhost:4318`. Spans are
+    ///   sent to `/v1/traces`.
+    /// * `service_name`: Service name of the exported spans.
+    /// * `client`: HTTP client to send the spans to the collector with.
+    ///
+    /// # Returns
+    ///
+    /// The new `OtlpExporter` instance.
+    pub fn new(endpoint: &str, service_name: &str, client: Client) -> Arc<Self> {
+        let exporter = Arc::new(OtlpExporter {
+            endpoint: format!("{}/v1/traces", endpoint.trim_end_matches('/')),
+            service_name: service_name.to_owned(),
+            spans: Mutex::new(Vec::new()),
+            batch_full: Notify::new(),
+            dropped: AtomicU64::new(0),
+            client,
+        });
+        let weak = Arc::downgrade(&exporter);
+        tokio::spawn(Self::export_periodically(weak));
+        exporter
+    }
+
+    async fn export_periodically(exporter: Weak<Self>) {
+        let mut interval = tokio::time::interval(EXPORT_INTERVAL);
+        interval.tick().await;
+        loop {
+            let Some(notified) = exporter.upgrade() else {
+                break;
+            };
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = notified.batch_full.notified() => {}
+            }
+            drop(notified);
+            let Some(exporter) = exporter.upgrade() else {
+                break;
+            };
+            exporter.export_buffered().await;
+        }
+    }
+
+    /// Exports the buffered spans in batches and counts the spans of the failed batches as
+    /// dropped.
+    async fn export_buffered(&self) {
+        let spans = std::mem::take(&mut *self.spans.lock().unwrap());
+        for batch in spans.chunks(MAX_BATCH_SIZE) {
+            if self.export(batch).await.is_err() {
+                self.dropped
+                    .fetch_add(batch.len() as u64, Ordering::Relaxed);
+            }
+        }
+    }
+
+    async fn export(&self, spans: &[Value]) -> Result<()> {
+        let request = json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [Self::attribute("service.name", json!({"stringValue": self.service_name}))],
+                },
+                "scopeSpans": [{
+                    "scope": {"name": "load-rs", "version": env!("CARGO_PKG_VERSION")},
+                    "spans": spans,
+                }],
+            }],
+        });
+        self.client
+            .post(&self.endpoint)
+            .header(CONTENT_TYPE, "application/json")
+            .body(request.to_string())
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn to_span(sample: &Sample, trace: &TraceContext) -> Value {
+        let start = trace
+            .start_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let end = start + sample.duration;
+        let method = trace.method.to_string();
+        let mut attributes = vec![
+            Self::attribute("http.request.method", json!({"stringValue": method})),
+            Self::attribute("url.path", json!({"stringValue": sample.endpoint})),
+        ];
+        if let Some(status) = sample.status {
+            attributes.push(Self::attribute(
+                "http.response.status_code",
+                json!({"intValue": status.to_string()}),
+            ));
+        }
+        json!({
+            "traceId": trace.trace_id,
+            "spanId": trace.span_id,
+            "name": method,
+            // SPAN_KIND_CLIENT
+            "kind": 3,
+            "startTimeUnixNano": start.as_nanos().to_string(),
+            "endTimeUnixNano": end.as_nanos().to_string(),
+            "attributes": attributes,
+            // STATUS_CODE_OK or STATUS_CODE_ERROR
+            "status": {"code": if sample.success { 1 } else { 2 }},
+        })
+    }
+
+    fn attribute(key: &str, value: Value) -> Value {
+        json!({"key": key, "value": value})
+    }
+}
+
+impl Recorder for OtlpExporter {
+    fn record(&self, sample: &Sample) {
+        let Some(trace) = &sample.trace else {
+            return;
+        };
+        if !trace.sampled {
+            return;
+        }
+        let span = Self::to_span(sample, trace);
+        let mut spans = self.spans.lock().unwrap();
+        if spans.len() >= MAX_BUFFERED_SPANS {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        spans.push(span);
+        if spans.len() >= MAX_BATCH_SIZE {
+            self.batch_full.notify_one();
+        }
+    }
+
+    fn finish(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.export_buffered().await;
+            Ok(())
+        })
+    }
+
+    fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sample_ratio_succeeds() {
+        assert_eq!(parse_sample_ratio("0.25").unwrap(), 0.25);
+        assert_eq!(parse_sample_ratio("1").unwrap(), 1.0);
+        assert_eq!(
+            parse_sample_ratio("NaN").unwrap_err().to_string(),
+            "Sample ratio 'NaN' must be between 0.0 and 1.0"
+        );
+        assert_eq!(
+            parse_sample_ratio("1.5").unwrap_err().to_string(),
+            "Sample ratio '1.5' must be between 0.0 and 1.0"
+        );
+        assert_eq!(
+            parse_sample_ratio("half").unwrap_err().to_string(),
+            "'half' is not a valid sample ratio"
+        );
+    }
+
+    #[test]
+    fn traceparent_succeeds() {
+        let trace = TraceContext::new(HttpMethod::Get, true);
+        let traceparent = trace.traceparent();
+        let parts: Vec<&str> = traceparent.split('-').collect();
+
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0], "00");
+        assert_eq!(parts[1].len(), 32);
+        assert_eq!(parts[2].len(), 16);
+        assert_eq!(parts[3], "01");
+        assert_eq!(
+            TraceContext::new(HttpMethod::Get, false).traceparent()[53..],
+            *"00"
+        );
+    }
+
+    #[test]
+    fn trace_ids_are_unique() {
+        let trace1 = TraceContext::new(HttpMethod::Get, true);
+        let trace2 = TraceContext::new(HttpMethod::Get, true);
+
+        assert_ne!(trace1.trace_id, trace2.trace_id);
+        assert_ne!(trace1.span_id, trace2.span_id);
+    }
+
+    #[test]
+    fn to_span_succeeds() {
+        let trace = TraceContext {
+            trace_id: "0af7651916cd43dd8448eb211c80319c".to_owned(),
+            span_id: "b7ad6b7169203331".to_owned(),
+            sampled: true,
+            method: HttpMethod::Post,
+            start_time: UNIX_EPOCH + Duration::from_secs(1),
+        };
+        let sample = Sample {
+            name: "POST /post".to_owned(),
+            endpoint: "/post".to_owned(),
+            status: Some(201),
+            success: true,
+            duration: Duration::from_millis(5),
+            timestamp: SystemTime::now(),
+            trace: Some(trace.clone()),
+        };
+
+        let span = OtlpExporter::to_span(&sample, &trace);
+
+        assert_eq!(span["traceId"], "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(span["spanId"], "b7ad6b7169203331");
+        assert_eq!(span["name"], "POST");
+        assert_eq!(span["kind"], 3);
+        assert_eq!(span["startTimeUnixNano"], "1000000000");
+        assert_eq!(span["endTimeUnixNano"], "1005000000");
+        assert_eq!(span["status"]["code"], 1);
+        assert_eq!(span["attributes"][2]["value"]["intValue"], "201");
+    }
+
+    #[tokio::test]
+    async fn record_drops_spans_when_buffer_is_full() {
+        let exporter = OtlpExporter::new("http://127.0.0.1:1", "load-rs", Client::new());
+        let sample = Sample {
+            name: "GET /get".to_owned(),
+            endpoint: "/get".to_owned(),
+            status: Some(200),
+            success: true,
+            duration: Duration::from_millis(5),
+            timestamp: SystemTime::now(),
+            trace: Some(TraceContext::new(HttpMethod::Get, true)),
+        };
+
+        for _ in 0..MAX_BUFFERED_SPANS + 10 {
+            exporter.record(&sample);
+        }
+        assert_eq!(exporter.spans.lock().unwrap().len(), MAX_BUFFERED_SPANS);
+        assert_eq!(exporter.dropped(), 10);
+
+        // The collector is unreachable, so the buffered spans are dropped, too.
+        exporter.finish().await.unwrap();
+        assert!(exporter.spans.lock().unwrap().is_empty());
+        assert_eq!(exporter.dropped(), MAX_BUFFERED_SPANS as u64 + 10);
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{Context, Result, bail};
+use bytes::BytesMut;
+use futures::stream;
+use std::path::Path;
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tokio::time::Instant;
+
+/// Default size of the chunks of a streamed request body.
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Maximum size of the chunks of a streamed request body, because every concurrent upload holds
+/// a chunk in memory.
+pub const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Configuration of request bodies that are streamed from disk in chunks instead of being read
+/// into memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyStreaming {
+    /// Size of the chunks in bytes.
+    pub chunk_size: usize,
+
+    /// Maximum upload bandwidth of a request in bytes per second, e.g. to simulate slow clients.
+    pub upload_rate: Option<u64>,
+}
+
+impl Default for BodyStreaming {
+    fn default() -> Self {
+        BodyStreaming {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            upload_rate: None,
+        }
+    }
+}
+
+impl BodyStreaming {
+    /// Opens a file as a chunked request body.
+    ///
+    /// # Parameters
+    ///
+    /// * `path`: File to stream.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the body and the size of the file if successful.
+    pub(crate) async fn open(&self, path: &Path) -> Result<(reqwest::Body, u64)> {
+        let file = File::open(path)
+            .await
+            .context(format!("Failed to open data file '{}'", path.display()))?;
+        let len = file.metadata().await?.len();
+        let chunk_size = self.chunk_size;
+        let upload_rate = self.upload_rate;
+        // The upload rate is measured from the first chunk, and the file is dropped after the
+        // first error, which ends the stream.
+        let state = (Some(file), 0u64, None::<Instant>);
+        let chunks = stream::unfold(state, move |(file, sent, start)| async move {
+            let mut file = file?;
+            let start = start.unwrap_or_else(Instant::now);
+            // The last chunk is only as large as the rest of the file, but at least one byte, so
+            // that the end of the file is detected.
+            let remaining = usize::try_from(len.saturating_sub(sent)).unwrap_or(usize::MAX);
+            let mut chunk = BytesMut::with_capacity(chunk_size.min(remaining).max(1));
+            let n = match file.read_buf(&mut chunk).await {
+                Ok(0) => return None,
+                Ok(n) => n,
+                Err(e) => return Some((Err(e), (None, sent, Some(start)))),
+            };
+            let sent = sent + n as u64;
+            if let Some(upload_rate) = upload_rate {
+                let due = start + Duration::from_secs_f64(sent as f64 / upload_rate as f64);
+                tokio::time::sleep_until(due).await;
+            }
+            Some((Ok(chunk.freeze()), (Some(file), sent, Some(start))))
+        });
+        Ok((reqwest::Body::wrap_stream(chunks), len))
+    }
+}
+
+/// Parses the size of the chunks of a streamed request body, which is at most
+/// [`MAX_CHUNK_SIZE`].
+pub fn parse_chunk_size(s: &str) -> Result<u64> {
+    let size = parse_size(s)?;
+    if size > MAX_CHUNK_SIZE as u64 {
+        bail!("Chunk size '{s}' cannot be larger than 16m");
+    }
+    Ok(size)
+}
+
+/// Parses a number of bytes with an optional binary suffix, e.g. `512`, `64k`, `1m` or `2g`.
+pub fn parse_size(s: &str) -> Result<u64> {
+    let lower = s.trim().to_ascii_lowercase();
+    let lower = lower.strip_suffix('b').unwrap_or(&lower);
+    let (number, multiplier) = match lower.char_indices().last() {
+        Some((i, 'k')) => (&lower[..i], 1 << 10),
+        Some((i, 'm')) => (&lower[..i], 1 << 20),
+        Some((i, 'g')) => (&lower[..i], 1 << 30),
+        _ => (lower, 1),
+    };
+    match number.parse::<u64>() {
+        Ok(size) if size > 0 => size
+            .checked_mul(multiplier)
+            .context(format!("'{s}' is too large")),
+        _ => bail!("'{s}' is not a valid size"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use http_body_util::BodyExt;
+
+    #[test]
+    fn parse_size_succeeds() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("64k").unwrap(), 64 * 1024);
+        assert_eq!(parse_size("1MB").unwrap(), 1024 * 1024);
+        assert_eq!(parse_size("2g").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert_eq!(
+            parse_size("0").unwrap_err().to_string(),
+            "'0' is not a valid size"
+        );
+        assert_eq!(
+            parse_size("fast").unwrap_err().to_string(),
+            "'fast' is not a valid size"
+        );
+    }
+
+    #[test]
+    fn parse_chunk_size_succeeds() {
+        assert_eq!(parse_chunk_size("16m").unwrap(), 16 * 1024 * 1024);
+        assert_eq!(
+            parse_chunk_size("2g").unwrap_err().to_string(),
+            "Chunk size '2g' cannot be larger than 16m"
+        );
+    }
+
+    #[tokio::test]
+    async fn open_allocates_at_most_the_file_size() {
+        let path = "tests/test_requests/test1.json";
+        let content = tokio::fs::read(path).await.unwrap();
+        let streaming = BodyStreaming {
+            chunk_size: MAX_CHUNK_SIZE,
+            upload_rate: None,
+        };
+
+        let (body, _) = streaming.open(Path::new(path)).await.unwrap();
+        let mut frames = body.into_data_stream();
+        let chunk = frames.next().await.unwrap().unwrap();
+
+        assert_eq!(chunk, content);
+        assert!(frames.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn open_streams_chunks_at_upload_rate() {
+        let path = "tests/test_requests/test1.json";
+        let content = tokio::fs::read(path).await.unwrap();
+        // The whole file takes 100ms to upload.
+        let streaming = BodyStreaming {
+            chunk_size: 10,
+            upload_rate: Some(content.len() as u64 * 10),
+        };
+
+        let start = Instant::now();
+        let (body, len) = streaming.open(Path::new(path)).await.unwrap();
+        let mut frames = body.into_data_stream();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = frames.next().await {
+            chunks.push(chunk.unwrap());
+        }
+
+        assert_eq!(len, content.len() as u64);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 10));
+        assert_eq!(chunks.concat(), content);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -1,7 +1,47 @@
 use anyhow::Result;
 use assert_cmd::Command;
 use predicates::prelude::predicate;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener};
 use std::path::PathBuf;
+use std::thread;
+
+/// Runs a server that responds to a single request with the raw request as the body.
+fn run_raw_echo_server() -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    thread::spawn(move || -> Result<()> {
+        let (stream, _) = listener.accept()?;
+        let mut reader = BufReader::new(stream);
+        let mut request = Vec::new();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse()?;
+            }
+            request.extend_from_slice(line.as_bytes());
+            if line == "\r\n" || line.is_empty() {
+                break;
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        request.extend_from_slice(&body);
+        let mut stream = reader.into_inner();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            request.len()
+        )?;
+        stream.write_all(&request)?;
+        Ok(())
+    });
+    Ok(addr)
+}
 
 #[test]
 fn run_get() -> Result<()> {
@@ -215,6 +255,71 @@ fn run_data_dir_random() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn run_url_file_random() -> Result<()> {
+    let mut cmd = Command::cargo_bin("load-rs")?;
+    cmd.args([
+        "-n",
+        "5",
+        "-c",
+        "2",
+        "-X",
+        "GET",
+        "--url-file",
+        "tests/test_urls/urls1.txt",
+        "-O",
+        "random",
+        "https://mockhttp.org",
+    ]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Sending 5 requests to https://mockhttp.org with 2 concurrency",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn run_url_file_with_data_dir_fails() -> Result<()> {
+    let mut cmd = Command::cargo_bin("load-rs")?;
+    cmd.args([
+        "--url-file",
+        "tests/test_urls/urls1.txt",
+        "-i",
+        "tests/test_requests",
+        "https://mockhttp.org",
+    ]);
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "the argument '--url-file <URL_FILE>' cannot be used with '--data-dir <DATA_DIR>'",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn run_url_file_and_data_file_from_stdin_fails() -> Result<()> {
+    let mut cmd = Command::cargo_bin("load-rs")?;
+    cmd.args([
+        "-n",
+        "1",
+        "-c",
+        "1",
+        "--url-file",
+        "-",
+        "-D",
+        "-",
+        "http://localhost:1",
+    ])
+    .write_stdin("/products/1\n");
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "URL file and data file cannot both be read from stdin",
+    ));
+
+    Ok(())
+}
+
 #[test]
 fn debug_get() -> Result<()> {
     let mut cmd = Command::cargo_bin("load-rs")?;
@@ -436,6 +541,71 @@ fn debug_data_dir_random() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn debug_multipart_form() -> Result<()> {
+    let addr = run_raw_echo_server()?;
+    let mut cmd = Command::cargo_bin("load-rs")?;
+    cmd.args([
+        "--debug",
+        "-n",
+        "1",
+        "-c",
+        "1",
+        "-X",
+        "POST",
+        "-F",
+        "title=Holiday",
+        "-F",
+        "file=@tests/test_requests/test1.json;type=application/json",
+        &format!("http://{addr}/upload"),
+    ]);
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output)?;
+    let boundary = output
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(": ")?;
+            if !name.eq_ignore_ascii_case("content-type") {
+                return None;
+            }
+            value.strip_prefix("multipart/form-data; boundary=")
+        })
+        .expect("multipart Content-Type header");
+    let file = std::fs::read_to_string("tests/test_requests/test1.json")?;
+    assert!(output.contains(&format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nHoliday\r\n\
+         --{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"test1.json\"\r\nContent-Type: application/json\r\n\r\n{file}\r\n\
+         --{boundary}--\r\n"
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn debug_manifest_from_stdin() -> Result<()> {
+    let addr = run_raw_echo_server()?;
+    let mut cmd = Command::cargo_bin("load-rs")?;
+    cmd.args([
+        "--debug",
+        "-n",
+        "1",
+        "-c",
+        "1",
+        "-m",
+        "-",
+        &format!("http://{addr}"),
+    ])
+    .write_stdin("{\"method\": \"PUT\", \"path\": \"/items/1\", \"body\": \"hello\"}\n");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("PUT /items/1 HTTP/1.1"))
+        .stdout(predicate::str::contains("hello"));
+
+    Ok(())
+}
+
 #[test]
 fn run_save_responses() -> Result<()> {
     let dir = "/tmp/load-rs/cli1";
@@ -536,3 +706,73 @@ fn run_manifest_save_responses() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn validate_manifest() -> Result<()> {
+    let mut cmd = Command::cargo_bin("load-rs")?;
+    cmd.args(["validate", "-m", "tests/test_manifests/manifest4.jsonl"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("4 valid and 0 invalid lines"))
+        .stdout(predicate::str::contains(
+            "read: Requests: 1 | Weight: 8 (72.7%)",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn validate_invalid_manifest() -> Result<()> {
+    let mut cmd = Command::cargo_bin("load-rs")?;
+    cmd.args(["validate", "-m", "tests/test_manifests/manifest5.jsonl"]);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("1 valid and 2 invalid lines"))
+        .stderr(predicate::str::contains("Line 2: 'FETCH'"))
+        .stderr(predicate::str::contains(
+            "Line 3: Invalid header name 'Bad Name'",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn dry_run_manifest_with_feeder() -> Result<()> {
+    let mut cmd = Command::cargo_bin("load-rs")?;
+    cmd.args([
+        "-n",
+        "1",
+        "-c",
+        "1",
+        "-m",
+        "tests/test_manifests/manifest2.jsonl",
+        "--feeder",
+        "tests/test_feeders/users.csv",
+        "--dry-run",
+        "http://localhost:1",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("1 valid and 0 invalid lines"));
+
+    Ok(())
+}
+
+#[test]
+fn validate_manifest_from_stdin() -> Result<()> {
+    let mut cmd = Command::cargo_bin("load-rs")?;
+    cmd.args(["validate", "-m", "-"])
+        .write_stdin(std::fs::read("tests/test_manifests/manifest3.jsonl")?);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("3 valid and 0 invalid lines in -"))
+        .stdout(predicate::str::contains(
+            "POST /cart: Requests: 1 | Weight: 1 (33.3%)",
+        ));
+
+    Ok(())
+}
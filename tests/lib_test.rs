@@ -1,23 +1,38 @@
 extern crate load_rs;
 
 use anyhow::{Context, Result};
-use http_body_util::Full;
+use http_body_util::{BodyExt, Full};
 use hyper::Response;
-use hyper::body::Bytes;
+use hyper::body::{Bytes, Incoming};
 use hyper::service::service_fn;
 use hyper_util::rt::{TokioExecutor, TokioIo};
-use load_rs::Body::{Data, DataFile};
-use load_rs::{HttpMethod, LoadTestRunner, Order, Stats};
-use reqwest::header::HeaderMap;
+use load_rs::Body::{Data, DataFile, Multipart, UrlEncoded};
+use load_rs::access_log::AccessLog;
+use load_rs::compression::BodyCompression;
+use load_rs::curl::CurlCommand;
+use load_rs::data_source::PickMode;
+use load_rs::feeder::Feeder;
+use load_rs::har::{Har, HarFilter};
+use load_rs::openapi::OpenApi;
+use load_rs::postman::PostmanCollection;
+use load_rs::prometheus::PrometheusExporter;
+use load_rs::scenario::Scenario;
+use load_rs::think_time::ThinkTime;
+use load_rs::upload::BodyStreaming;
+use load_rs::{HttpMethod, LoadTestRunner, Order, Replay, RequestIdFormat, Stats};
+use reqwest::header::{HeaderMap, HeaderName};
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
 use rustls::server::WebPkiClientVerifier;
 use rustls::{RootCertStore, ServerConfig};
 use rustls_pemfile::{certs, private_key};
+use serde_json::json;
+use std::collections::HashMap;
 use std::convert::Infallible;
 use std::io::BufReader;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::fs;
 use tokio::net::TcpListener;
 use tokio::sync::{OnceCell, oneshot};
@@ -125,6 +140,70 @@ async fn run_server(version: HttpVersion) -> Result<TestServer> {
     })
 }
 
+/// Runs a plain HTTP/1.1 server that echoes the request back as a JSON object.
+async fn run_echo_server() -> Result<TestServer> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+    let listener = TcpListener::bind(addr).await?;
+    let server_addr = listener.local_addr()?;
+    let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+               res = listener.accept() => {
+                    let (stream, _peer_addr) = match res {
+                        Ok(res) => res,
+                        Err(_) => continue,
+                    };
+                    tokio::spawn(async move {
+                        let io = TokioIo::new(stream);
+                        hyper::server::conn::http1::Builder::new()
+                            .serve_connection(io, service_fn(echo))
+                            .await
+                            .ok();
+                    });
+                },
+                _ = &mut shutdown_rx => {
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(TestServer {
+        addr: server_addr,
+        shutdown_tx: Some(shutdown_tx),
+    })
+}
+
+async fn echo(req: hyper::Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+    let method = req.method().to_string();
+    let path = req.uri().to_string();
+    let headers: HashMap<String, String> = req
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_str().unwrap_or("").to_string()))
+        .collect();
+    let body = req.into_body().collect().await.unwrap().to_bytes();
+    let output = json!({
+        "method": method,
+        "path": path,
+        "headers": headers,
+        "body": String::from_utf8_lossy(&body),
+    });
+    let status = if path.starts_with("/fail") { 500 } else { 200 };
+    let mut response = Response::builder().status(status);
+    if let Some(session) = path
+        .strip_prefix("/login/")
+        .or_else(|| path.strip_prefix("/fail/login/"))
+    {
+        response = response.header("set-cookie", format!("session={session}; Path=/"));
+    }
+    Ok(response
+        .body(Full::new(Bytes::from(output.to_string())))
+        .unwrap())
+}
+
 async fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
     let cert = fs::read(path).await?;
     let mut reader = BufReader::new(cert.as_slice());
@@ -162,10 +241,10 @@ async fn run_get() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -191,10 +270,10 @@ async fn run_head() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -228,10 +307,10 @@ async fn run_post() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -265,10 +344,10 @@ async fn run_put() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -302,10 +381,10 @@ async fn run_patch() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -339,10 +418,10 @@ async fn run_delete() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -377,10 +456,10 @@ async fn run_from_dir_sequential() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -414,10 +493,10 @@ async fn run_from_data_file() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -452,10 +531,10 @@ async fn run_from_dir_random() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -490,10 +569,10 @@ async fn run_from_dir_requests_less_than_files_sequential() {
     assert_eq!(result.success, 3);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 3);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -528,10 +607,10 @@ async fn run_from_dir_requests_greater_than_files_sequential() {
     assert_eq!(result.success, 7);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 7);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -566,10 +645,10 @@ async fn run_from_dir_requests_less_than_files_random() {
     assert_eq!(result.success, 3);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 3);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -604,10 +683,10 @@ async fn run_from_dir_requests_greater_than_files_random() {
     assert_eq!(result.success, 7);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 7);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -639,10 +718,10 @@ async fn run_from_manifest_random() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -674,10 +753,10 @@ async fn run_from_manifest_requests_less_than_files_sequential() {
     assert_eq!(result.success, 3);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 3);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -709,10 +788,10 @@ async fn run_from_manifest_requests_greater_than_files_sequential() {
     assert_eq!(result.success, 7);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 7);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -744,10 +823,10 @@ async fn run_from_manifest_requests_less_than_files_random() {
     assert_eq!(result.success, 3);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 3);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -779,10 +858,10 @@ async fn run_from_manifest_requests_greater_than_files_random() {
     assert_eq!(result.success, 7);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 7);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -1347,10 +1426,10 @@ async fn run_mtls_http1_valid_certs() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -1378,10 +1457,10 @@ async fn run_mtls_http2_valid_certs() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -1443,10 +1522,10 @@ async fn run_success_stats() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 
     // Failed requests with `Stats::Success`.
     let runner = LoadTestRunner::new(
@@ -1478,10 +1557,10 @@ async fn run_success_stats() {
     assert_eq!(result.success, 0);
     assert_eq!(result.failures, 5);
     assert_eq!(result.completed, 5);
-    assert_eq!(result.p50, Default::default());
-    assert_eq!(result.p90, Default::default());
-    assert_eq!(result.p95, Default::default());
-    assert_eq!(result.avg, Default::default());
+    assert_eq!(result.p50, Duration::default());
+    assert_eq!(result.p90, Duration::default());
+    assert_eq!(result.p95, Duration::default());
+    assert_eq!(result.avg, Duration::default());
 }
 
 #[tokio::test]
@@ -1516,10 +1595,10 @@ async fn run_failure_stats() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert_eq!(result.p50, Default::default());
-    assert_eq!(result.p90, Default::default());
-    assert_eq!(result.p95, Default::default());
-    assert_eq!(result.avg, Default::default());
+    assert_eq!(result.p50, Duration::default());
+    assert_eq!(result.p90, Duration::default());
+    assert_eq!(result.p95, Duration::default());
+    assert_eq!(result.avg, Duration::default());
 
     // Failed requests with `Stats::Error`.
     let runner = LoadTestRunner::new(
@@ -1551,10 +1630,10 @@ async fn run_failure_stats() {
     assert_eq!(result.success, 0);
     assert_eq!(result.failures, 5);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 }
 
 #[tokio::test]
@@ -1589,10 +1668,10 @@ async fn run_all_stats() {
     assert_eq!(result.success, 5);
     assert_eq!(result.failures, 0);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
 
     // Failed requests with `Stats::Success`.
     let runner = LoadTestRunner::new(
@@ -1624,8 +1703,1687 @@ async fn run_all_stats() {
     assert_eq!(result.success, 0);
     assert_eq!(result.failures, 5);
     assert_eq!(result.completed, 5);
-    assert!(result.p50 > Default::default());
-    assert!(result.p90 > Default::default());
-    assert!(result.p95 > Default::default());
-    assert!(result.avg > Default::default());
+    assert!(result.p50 > Duration::default());
+    assert!(result.p90 > Duration::default());
+    assert!(result.p95 > Duration::default());
+    assert!(result.avg > Duration::default());
+}
+
+#[tokio::test]
+async fn run_prometheus_metrics() {
+    let test_server = run_echo_server().await.unwrap();
+    let exporter = Arc::new(PrometheusExporter::new());
+    let metrics_addr = exporter
+        .clone()
+        .serve(SocketAddr::from(([127, 0, 0, 1], 0)))
+        .await
+        .unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/get", test_server.addr).as_str(),
+        5,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_recorder(exporter);
+
+    let result = runner
+        .run(HttpMethod::Get, None, None, &None, |_| {})
+        .await
+        .unwrap();
+    let metrics = reqwest::get(format!("http://{metrics_addr}/metrics"))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 5);
+    assert!(
+        metrics.contains(
+            "load_rs_requests_total{name=\"GET /get\",endpoint=\"/get\",status=\"200\"} 5"
+        )
+    );
+    assert!(
+        metrics
+            .contains("load_rs_request_duration_seconds_count{name=\"GET /get\",endpoint=\"/get\",status=\"200\"} 5")
+    );
+}
+
+#[tokio::test]
+async fn run_trace_context_save_responses() {
+    let dir = "/tmp/load-rs/lib7";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/get", test_server.addr).as_str(),
+        3,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_trace_context(1.0)
+    .unwrap();
+
+    let result = runner
+        .run(HttpMethod::Get, None, None, &Some(output_dir), |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 3);
+    for i in 1..=3 {
+        let output = fs::read_to_string(format!("{dir}/success-{i}.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let trace_id = output["trace_id"].as_str().unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        let traceparent = body["headers"]["traceparent"].as_str().unwrap();
+        assert_eq!(trace_id.len(), 32);
+        assert!(traceparent.starts_with(&format!("00-{trace_id}-")));
+        assert!(traceparent.ends_with("-01"));
+    }
+}
+
+#[tokio::test]
+async fn run_request_id_save_responses() {
+    let dir = "/tmp/load-rs/lib8";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/get", test_server.addr).as_str(),
+        3,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_request_id(
+        HeaderName::from_static("x-request-id"),
+        RequestIdFormat::Iteration,
+        "run1",
+    );
+
+    let result = runner
+        .run(HttpMethod::Get, None, None, &Some(output_dir), |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 3);
+    for i in 1..=3 {
+        let output = fs::read_to_string(format!("{dir}/success-{i}.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(output["request_id"], format!("run1-{i}"));
+        assert_eq!(body["headers"]["x-request-id"], format!("run1-{i}"));
+    }
+}
+
+#[tokio::test]
+async fn run_request_id_uuid() {
+    let dir = "/tmp/load-rs/lib9";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/get", test_server.addr).as_str(),
+        2,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_request_id(
+        HeaderName::from_static("x-request-id"),
+        RequestIdFormat::Uuid,
+        "run1",
+    );
+
+    runner
+        .run(HttpMethod::Get, None, None, &Some(output_dir), |_| {})
+        .await
+        .unwrap();
+
+    let mut request_ids = Vec::new();
+    for i in 1..=2 {
+        let output = fs::read_to_string(format!("{dir}/success-{i}.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        request_ids.push(output["request_id"].as_str().unwrap().to_owned());
+    }
+    assert_eq!(request_ids[0].len(), 36);
+    assert_ne!(request_ids[0], request_ids[1]);
+}
+
+#[tokio::test]
+async fn run_templating_save_responses() {
+    let dir = "/tmp/load-rs/lib10";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/items/{{{{seq}}}}", test_server.addr).as_str(),
+        3,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_templating();
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-token", "{{random_string 16}}".parse().unwrap());
+    let result = runner
+        .run(
+            HttpMethod::Post,
+            Some(headers),
+            Some(Data("{\"seq\": {{seq}}}".into())),
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 3);
+    for i in 1..=3 {
+        let output = fs::read_to_string(format!("{dir}/success-{i}.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["path"], format!("/items/{i}"));
+        assert_eq!(body["body"], format!("{{\"seq\": {i}}}"));
+        assert_eq!(body["headers"]["x-token"].as_str().unwrap().len(), 16);
+    }
+}
+
+#[tokio::test]
+async fn run_from_manifest_templating_save_responses() {
+    let dir = "/tmp/load-rs/lib11";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/post", test_server.addr).as_str(),
+        2,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_templating();
+
+    let result = runner
+        .run_from_manifest(
+            HttpMethod::Post,
+            &"tests/test_manifests/manifest2.jsonl".into(),
+            Order::Sequential,
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 2);
+    let mut request_ids = Vec::new();
+    for i in 1..=2 {
+        let output = fs::read_to_string(format!("{dir}/success-{i}.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        let request_body: serde_json::Value =
+            serde_json::from_str(body["body"].as_str().unwrap()).unwrap();
+        assert_eq!(request_body["seq"], i);
+        assert!((1..=1000).contains(&request_body["n"].as_i64().unwrap()));
+        request_ids.push(body["headers"]["x-request-id"].as_str().unwrap().to_owned());
+    }
+    assert_ne!(request_ids[0], request_ids[1]);
+}
+
+#[tokio::test]
+async fn run_from_manifest_invalid_template_fails() {
+    let runner = LoadTestRunner::new(
+        "http://localhost:8080",
+        2,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_templating();
+
+    let path = std::env::temp_dir().join("load-rs-invalid-template.jsonl");
+    fs::write(&path, "{\"body\": \"{{foo 1}}\"}\n")
+        .await
+        .unwrap();
+    let err = runner
+        .run_from_manifest(HttpMethod::Post, &path, Order::Sequential, &None, |_| {})
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Invalid request on line 1 of manifest file '{}'",
+            path.display()
+        )
+    );
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Unknown template function 'foo'"
+    );
+}
+
+#[tokio::test]
+async fn run_feeder_save_responses() {
+    let dir = "/tmp/load-rs/lib12";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+    let feeder = Feeder::from_file(
+        Path::new("tests/test_feeders/users.csv"),
+        PickMode::Sequential,
+    )
+    .await
+    .unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/users/{{{{user_id}}}}", test_server.addr).as_str(),
+        3,
+        1,
+        Stats::All,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_feeder(feeder);
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-token", "{{token}}".parse().unwrap());
+    let result = runner
+        .run(
+            HttpMethod::Post,
+            Some(headers),
+            Some(Data("{\"user_id\": {{user_id}}}".into())),
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 2);
+    assert_eq!(result.failures, 1);
+    for (i, (user_id, token)) in [("1", "abc"), ("2", "def")].into_iter().enumerate() {
+        let output = fs::read_to_string(format!("{dir}/success-{}.json", i + 1))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["path"], format!("/users/{user_id}"));
+        assert_eq!(body["body"], format!("{{\"user_id\": {user_id}}}"));
+        assert_eq!(body["headers"]["x-token"], token);
+    }
+}
+
+#[tokio::test]
+async fn run_feeder_unknown_variable_fails() {
+    let feeder = Feeder::from_file(
+        Path::new("tests/test_feeders/users.csv"),
+        PickMode::Circular,
+    )
+    .await
+    .unwrap();
+    let runner = LoadTestRunner::new(
+        "http://localhost:8080/users/{{sku}}",
+        2,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_feeder(feeder);
+
+    let err = runner
+        .run(HttpMethod::Get, None, None, &None, |_| {})
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "Unknown template variable 'sku'");
+}
+
+#[tokio::test]
+async fn run_from_manifest_mixed_endpoints_save_responses() {
+    let dir = "/tmp/load-rs/lib13";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        3,
+        1,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_from_manifest(
+            HttpMethod::Post,
+            &"tests/test_manifests/manifest3.jsonl".into(),
+            Order::Sequential,
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 3);
+    let expected = [
+        ("GET", "/items?page=1&q=red+shoes", ""),
+        ("POST", "/cart", "{\"sku\": \"42\"}"),
+        ("DELETE", "/cart/1", ""),
+    ];
+    for (i, (method, path, request_body)) in expected.into_iter().enumerate() {
+        let output = fs::read_to_string(format!("{dir}/success-{}.json", i + 1))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["method"], method);
+        assert_eq!(body["path"], path);
+        assert_eq!(body["body"], request_body);
+    }
+}
+
+#[tokio::test]
+async fn run_from_manifest_weighted() {
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        100,
+        10,
+        Stats::All,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_from_manifest(
+            HttpMethod::Get,
+            &"tests/test_manifests/manifest4.jsonl".into(),
+            Order::Weighted,
+            &None,
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.completed, 100);
+    assert!(!result.groups.contains_key("write"));
+    let read = &result.groups["read"];
+    let search = &result.groups["search"];
+    let fail = &result.groups["GET /fail"];
+    assert!(read.success > search.success);
+    assert_eq!(read.failures, 0);
+    assert_eq!(fail.success, 0);
+    assert_eq!(read.success + search.success + fail.failures, 100);
+    assert_eq!(read.durations.len() as u32, read.success);
+    assert!(read.p50 <= read.max);
+}
+
+#[tokio::test]
+async fn run_from_dir_groups() {
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/post", test_server.addr).as_str(),
+        7,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_from_dir(
+            HttpMethod::Post,
+            None,
+            &"tests/test_requests".into(),
+            Order::Sequential,
+            &None,
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 7);
+    assert_eq!(
+        result.groups.keys().collect::<Vec<_>>(),
+        vec!["test1", "test2", "test3", "test4", "test5"]
+    );
+    assert_eq!(result.groups["test1"].success, 2);
+    assert_eq!(result.groups["test2"].durations.len(), 2);
+    assert_eq!(result.groups["test3"].success, 1);
+}
+
+#[tokio::test]
+async fn run_groups_by_method_and_path() {
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/items?page=1", test_server.addr).as_str(),
+        3,
+        1,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run(HttpMethod::Get, None, None, &None, |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result.groups.len(), 1);
+    assert_eq!(result.groups["GET /items"].success, 3);
+}
+
+#[tokio::test]
+async fn run_scenario_save_responses() {
+    let dir = "/tmp/load-rs/lib14";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+    let scenario = Scenario::from_file(Path::new("tests/test_scenarios/scenario1.jsonl"))
+        .await
+        .unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        2,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_scenario(HttpMethod::Get, &scenario, &Some(output_dir), |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 6);
+    assert_eq!(result.groups["login"].success, 2);
+    assert_eq!(result.groups["create order"].success, 2);
+    assert_eq!(result.groups["fetch order"].success, 2);
+    for i in 1..=2 {
+        let output = fs::read_to_string(format!("{dir}/success-{i}-step3.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["method"], "GET");
+        assert_eq!(body["path"], format!("/orders/{i}"));
+        assert_eq!(body["headers"]["x-auth"], format!("Bearer tok-{i}"));
+    }
+}
+
+#[tokio::test]
+async fn run_scenario_failed_step_ends_iteration() {
+    let test_server = run_echo_server().await.unwrap();
+    let scenario = Scenario::from_file(Path::new("tests/test_scenarios/scenario2.jsonl"))
+        .await
+        .unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        3,
+        2,
+        Stats::All,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_scenario(HttpMethod::Get, &scenario, &None, |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result.completed, 3);
+    assert_eq!(result.failures, 3);
+    assert!(!result.groups.contains_key("fetch"));
+}
+
+#[tokio::test]
+async fn run_scenario_unknown_variable_fails() {
+    let path = std::env::temp_dir().join("load-rs-invalid-scenario.jsonl");
+    fs::write(
+        &path,
+        "{\"path\": \"/{{token}}\"}\n{\"extract\": {\"token\": {\"header\": \"x-token\"}}}\n",
+    )
+    .await
+    .unwrap();
+    let scenario = Scenario::from_file(&path).await.unwrap();
+    let runner = LoadTestRunner::new(
+        "http://localhost:8080",
+        2,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let err = runner
+        .run_scenario(HttpMethod::Get, &scenario, &None, |_| {})
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "Invalid step 1");
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Unknown template variable 'token'"
+    );
+}
+
+async fn run_virtual_users(dir: &str, shared_connections: Option<bool>) {
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+    let scenario = Scenario::from_file(Path::new("tests/test_scenarios/scenario3.jsonl"))
+        .await
+        .unwrap();
+
+    let mut runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        4,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+    if let Some(shared_connections) = shared_connections {
+        runner = runner.with_virtual_users(shared_connections);
+    }
+
+    let result = runner
+        .run_scenario(HttpMethod::Get, &scenario, &Some(output_dir), |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 8);
+    for i in 1..=4 {
+        let output = fs::read_to_string(format!("{dir}/success-{i}-step2.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        if shared_connections.is_some() {
+            assert_eq!(body["headers"]["cookie"], format!("session={i}"));
+        } else {
+            assert!(body["headers"]["cookie"].is_null());
+        }
+    }
+}
+
+#[tokio::test]
+async fn run_virtual_users_save_responses() {
+    run_virtual_users("/tmp/load-rs/lib15", Some(false)).await;
+}
+
+#[tokio::test]
+async fn run_virtual_users_shared_connections_save_responses() {
+    run_virtual_users("/tmp/load-rs/lib16", Some(true)).await;
+}
+
+#[tokio::test]
+async fn run_virtual_users_shared_connections_keep_error_cookies() {
+    let dir = "/tmp/load-rs/lib29";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    fs::create_dir_all(&output_dir).await.unwrap();
+    let url_file = output_dir.join("urls.txt");
+    fs::write(&url_file, "/fail/login/7\n/profile\n")
+        .await
+        .unwrap();
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        2,
+        1,
+        Stats::All,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_virtual_users(true);
+
+    let result = runner
+        .run_from_url_file(
+            HttpMethod::Get,
+            None,
+            None,
+            &url_file,
+            Order::Sequential,
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.failures, 1);
+    assert_eq!(result.success, 1);
+    let output = fs::read_to_string(format!("{dir}/success-2.json"))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let body: serde_json::Value = serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+    assert_eq!(body["headers"]["cookie"], "session=7");
+}
+
+#[tokio::test]
+async fn run_without_virtual_users_has_no_cookies() {
+    run_virtual_users("/tmp/load-rs/lib17", None).await;
+}
+
+#[tokio::test]
+async fn run_think_time_excluded_from_latency() {
+    let test_server = run_echo_server().await.unwrap();
+    let runner = LoadTestRunner::new(
+        format!("http://{}/get", test_server.addr).as_str(),
+        4,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_think_time(ThinkTime::Fixed(Duration::from_millis(300)));
+
+    let start = Instant::now();
+    let result = runner
+        .run(HttpMethod::Get, None, None, &None, |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 4);
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    assert!(result.max < Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn run_scenario_with_pacing() {
+    let test_server = run_echo_server().await.unwrap();
+    let scenario = Scenario::from_file(Path::new("tests/test_scenarios/scenario3.jsonl"))
+        .await
+        .unwrap();
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        3,
+        1,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_virtual_users(false)
+    .with_pacing(Duration::from_millis(200));
+
+    let start = Instant::now();
+    let result = runner
+        .run_scenario(HttpMethod::Get, &scenario, &None, |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 6);
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    assert!(result.max < Duration::from_millis(200));
+}
+
+async fn write_har(path: &str, addr: SocketAddr) -> PathBuf {
+    let har = json!({"log": {"entries": [
+        {
+            "startedDateTime": "2025-01-01T00:00:00Z",
+            "request": {
+                "method": "GET",
+                "url": format!("http://{addr}/items?page=1"),
+                "headers": [{"name": "X-Test", "value": "har"}]
+            },
+            "response": {"content": {"mimeType": "application/json"}}
+        },
+        {
+            "startedDateTime": "2025-01-01T00:00:00.300Z",
+            "request": {
+                "method": "POST",
+                "url": format!("http://{addr}/cart"),
+                "postData": {"mimeType": "application/json", "text": "{\"sku\": \"42\"}"}
+            },
+            "response": {"content": {"mimeType": "application/json"}}
+        },
+        {
+            "startedDateTime": "2025-01-01T00:00:00.100Z",
+            "request": {"method": "GET", "url": format!("http://{addr}/app.css")},
+            "response": {"content": {"mimeType": "text/css"}}
+        }
+    ]}});
+    let path = PathBuf::from(path);
+    fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+    fs::write(&path, har.to_string()).await.unwrap();
+    path
+}
+
+#[tokio::test]
+async fn run_from_har_save_responses() {
+    let dir = "/tmp/load-rs/lib18";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+    let path = write_har("/tmp/load-rs/har/lib18.har", test_server.addr).await;
+    let filter = HarFilter {
+        domains: vec![],
+        content_types: vec!["application/json".to_owned()],
+    };
+    let har = Har::from_file(&path, &filter).await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        2,
+        1,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_from_har(
+            HttpMethod::Get,
+            &har,
+            Replay::Order(Order::Sequential),
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 2);
+    let expected = [
+        ("GET", "/items?page=1", ""),
+        ("POST", "/cart", "{\"sku\": \"42\"}"),
+    ];
+    for (i, (method, path, request_body)) in expected.into_iter().enumerate() {
+        let output = fs::read_to_string(format!("{dir}/success-{}.json", i + 1))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["method"], method);
+        assert_eq!(body["path"], path);
+        assert_eq!(body["body"], request_body);
+    }
+}
+
+#[tokio::test]
+async fn run_from_har_preserve_timing() {
+    let test_server = run_echo_server().await.unwrap();
+    let path = write_har("/tmp/load-rs/har/timing.har", test_server.addr).await;
+    let har = Har::from_file(&path, &HarFilter::default()).await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        3,
+        3,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let start = Instant::now();
+    let result = runner
+        .run_from_har(HttpMethod::Get, &har, Replay::Timing(1.0), &None, |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 3);
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    assert_eq!(
+        result.groups.keys().collect::<Vec<_>>(),
+        vec!["GET /app.css", "GET /items", "POST /cart"]
+    );
+}
+
+#[tokio::test]
+async fn run_from_har_preserve_timing_loops() {
+    let test_server = run_echo_server().await.unwrap();
+    let path = write_har("/tmp/load-rs/har/timing-loops.har", test_server.addr).await;
+    let har = Har::from_file(&path, &HarFilter::default()).await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        4,
+        4,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let start = Instant::now();
+    let result = runner
+        .run_from_har(HttpMethod::Get, &har, Replay::Timing(1.0), &None, |_| {})
+        .await
+        .unwrap();
+
+    // The entries are at 0ms, 100ms and 300ms, so the second loop starts after the average gap
+    // of 150ms at 450ms.
+    assert_eq!(result.success, 4);
+    assert!(start.elapsed() >= Duration::from_millis(450));
+}
+
+#[tokio::test]
+async fn run_from_access_log_replay_speed() {
+    let dir = "/tmp/load-rs/lib19";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+    let access_log = AccessLog::from_file(Path::new("tests/test_access_logs/access1.log"))
+        .await
+        .unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        3,
+        1,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let start = Instant::now();
+    let result = runner
+        .run_from_access_log(
+            HttpMethod::Get,
+            &access_log,
+            Replay::Timing(2.0),
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 3);
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    assert!(start.elapsed() < Duration::from_millis(600));
+    let output = fs::read_to_string(format!("{dir}/success-1.json"))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let body: serde_json::Value = serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+    assert_eq!(body["method"], "GET");
+    assert_eq!(body["path"], "/items?page=1");
+    let output = fs::read_to_string(format!("{dir}/success-3.json"))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let body: serde_json::Value = serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+    assert_eq!(body["method"], "DELETE");
+    assert_eq!(body["path"], "/cart/1");
+}
+
+#[tokio::test]
+async fn run_from_curl_save_responses() {
+    let dir = "/tmp/load-rs/lib20";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+    let commands = CurlCommand::parse_all(&format!(
+        r#"curl 'http://{addr}/cart' \
+  -H 'Content-Type: application/json' \
+  --data-raw '{{"sku": "42"}}'
+curl -G http://{addr}/search --data-urlencode 'q=red shoes' -u alice:secret"#,
+        addr = test_server.addr
+    ))
+    .await
+    .unwrap();
+
+    let runner = LoadTestRunner::new(
+        commands[0].url(),
+        2,
+        1,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_from_curl(
+            HttpMethod::Get,
+            &commands,
+            Order::Sequential,
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 2);
+    let expected = [
+        ("POST", "/cart", "{\"sku\": \"42\"}"),
+        ("GET", "/search?q=red%20shoes", ""),
+    ];
+    for (i, (method, path, request_body)) in expected.into_iter().enumerate() {
+        let output = fs::read_to_string(format!("{dir}/success-{}.json", i + 1))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["method"], method);
+        assert_eq!(body["path"], path);
+        assert_eq!(body["body"], request_body);
+    }
+}
+
+#[tokio::test]
+async fn run_from_openapi_validate_responses() {
+    let dir = "/tmp/load-rs/lib21";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+    let openapi = OpenApi::from_file(Path::new("tests/test_openapi/orders.yaml"))
+        .await
+        .unwrap();
+
+    let runner = LoadTestRunner::new(
+        &format!("http://{}", test_server.addr),
+        2,
+        1,
+        Stats::All,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_from_openapi(
+            HttpMethod::Get,
+            &openapi,
+            Order::Sequential,
+            true,
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    // The echoed `getOrder` response has no `id`.
+    assert_eq!(result.success, 1);
+    assert_eq!(result.failures, 1);
+    let output = fs::read_to_string(format!("{dir}/success-1.json"))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let body: serde_json::Value = serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+    assert_eq!(body["method"], "POST");
+    assert_eq!(body["path"], "/api/orders");
+    assert_eq!(body["body"], r#"{"quantity":1,"sku":"42"}"#);
+    assert_eq!(body["headers"]["content-type"], "application/json");
+    let output = fs::read_to_string(format!("{dir}/failure-2.json"))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(
+        output["error"],
+        "Response body does not match the schema: $: missing required property 'id'"
+    );
+}
+
+#[tokio::test]
+async fn postman_collection_with_environment() {
+    let collection = PostmanCollection::from_file(
+        Path::new("tests/test_postman/shop.postman_collection.json"),
+        Some(Path::new(
+            "tests/test_postman/staging.postman_environment.json",
+        )),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        collection.to_manifest_lines().unwrap(),
+        vec![
+            r#"{"name":"Items / List items","method":"GET","url":"https://staging.example.com/items?page=1"}"#
+        ]
+    );
+    assert_eq!(
+        collection.warnings(),
+        ["The pre-request script of 'Items / List items' cannot be translated"]
+    );
+}
+
+#[tokio::test]
+async fn run_from_manifest_streaming() {
+    let dir = "/tmp/load-rs/lib22";
+    let manifest_file = PathBuf::from(format!("{dir}/manifest.jsonl"));
+    let output_dir = PathBuf::from(format!("{dir}/output"));
+    if Path::new(dir).exists() {
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+    fs::create_dir_all(dir).await.unwrap();
+    fs::write(
+        &manifest_file,
+        "{\"path\": \"/a\"}\n{\"path\": \"/b\"}\n{\"path\": \"/c\"}\n",
+    )
+    .await
+    .unwrap();
+    let test_server = run_echo_server().await.unwrap();
+    let url = format!("http://{}", test_server.addr);
+
+    // Without looping, the requests after the end of the manifest fail.
+    let runner = LoadTestRunner::new(&url, 5, 1, Stats::All, &None, &None, &None, &None)
+        .await
+        .unwrap()
+        .with_manifest_streaming(false, 2);
+    let result = runner
+        .run_from_manifest(
+            HttpMethod::Get,
+            &manifest_file,
+            Order::Sequential,
+            &None,
+            |_| {},
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.success, 3);
+    assert_eq!(result.failures, 2);
+
+    let runner = LoadTestRunner::new(&url, 5, 1, Stats::Success, &None, &None, &None, &None)
+        .await
+        .unwrap()
+        .with_manifest_streaming(true, 2);
+    let result = runner
+        .run_from_manifest(
+            HttpMethod::Get,
+            &manifest_file,
+            Order::Sequential,
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.success, 5);
+    for (i, path) in ["/a", "/b", "/c", "/a", "/b"].into_iter().enumerate() {
+        let output = fs::read_to_string(format!("{dir}/output/success-{}.json", i + 1))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["path"], path);
+    }
+}
+
+#[tokio::test]
+async fn run_multipart_form_save_responses() {
+    let dir = "/tmp/load-rs/lib23";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/upload", test_server.addr).as_str(),
+        2,
+        1,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_templating();
+
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", "text/plain".parse().unwrap());
+    let fields = vec![
+        "title=Upload {{seq}}".parse().unwrap(),
+        "file=@tests/test_requests/test1.json;type=application/json"
+            .parse()
+            .unwrap(),
+    ];
+    let result = runner
+        .run(
+            HttpMethod::Post,
+            Some(headers),
+            Some(Multipart(fields)),
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 2);
+    let file = fs::read_to_string("tests/test_requests/test1.json")
+        .await
+        .unwrap();
+    for i in 1..=2 {
+        let output = fs::read_to_string(format!("{dir}/success-{i}.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        let content_type = body["headers"]["content-type"].as_str().unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap();
+        assert_eq!(
+            body["body"],
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nUpload {i}\r\n\
+                 --{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"test1.json\"\r\nContent-Type: application/json\r\n\r\n{file}\r\n\
+                 --{boundary}--\r\n"
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn run_from_manifest_forms_save_responses() {
+    let dir = "/tmp/load-rs/lib24";
+    let manifest_file = PathBuf::from(format!("{dir}/manifest.jsonl"));
+    let output_dir = PathBuf::from(format!("{dir}/output"));
+    if Path::new(dir).exists() {
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+    fs::create_dir_all(dir).await.unwrap();
+    fs::write(
+        &manifest_file,
+        concat!(
+            r#"{"path": "/login", "form_urlencoded": {"user": "alice smith", "password": "a&b"}}"#,
+            "\n",
+            r#"{"path": "/upload", "form": {"file": "@tests/test_requests/test2.json"}}"#,
+            "\n",
+        ),
+    )
+    .await
+    .unwrap();
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        2,
+        1,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+    let result = runner
+        .run_from_manifest(
+            HttpMethod::Post,
+            &manifest_file,
+            Order::Sequential,
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 2);
+    let mut bodies = Vec::new();
+    for i in 1..=2 {
+        let output = fs::read_to_string(format!("{dir}/output/success-{i}.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        bodies.push(body);
+    }
+    assert_eq!(
+        bodies[0]["headers"]["content-type"],
+        "application/x-www-form-urlencoded"
+    );
+    assert_eq!(bodies[0]["body"], "password=a%26b&user=alice+smith");
+    assert!(
+        bodies[1]["headers"]["content-type"]
+            .as_str()
+            .unwrap()
+            .starts_with("multipart/form-data; boundary=")
+    );
+    assert!(
+        bodies[1]["body"]
+            .as_str()
+            .unwrap()
+            .contains("Content-Disposition: form-data; name=\"file\"; filename=\"test2.json\"\r\nContent-Type: application/octet-stream\r\n")
+    );
+}
+
+#[tokio::test]
+async fn run_body_compression_save_responses() {
+    let dir = "/tmp/load-rs/lib25";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/ingest", test_server.addr).as_str(),
+        3,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_templating()
+    .with_body_compression(BodyCompression::Gzip);
+
+    let body = format!(
+        "{{\"seq\": {{{{seq}}}}, \"data\": \"{}\"}}",
+        "a".repeat(1000)
+    );
+    let result = runner
+        .run(
+            HttpMethod::Post,
+            None,
+            Some(Data(body.into())),
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 3);
+    assert_eq!(result.uncompressed_bytes_sent, 3 * 1022);
+    assert!(result.bytes_sent > 0);
+    assert!(result.bytes_sent < result.uncompressed_bytes_sent / 10);
+    for i in 1..=3 {
+        let output = fs::read_to_string(format!("{dir}/success-{i}.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["headers"]["content-encoding"], "gzip");
+    }
+}
+
+#[tokio::test]
+async fn run_from_dir_body_streaming_save_responses() {
+    let dir = "/tmp/load-rs/lib26";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/upload", test_server.addr).as_str(),
+        3,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap()
+    .with_body_streaming(BodyStreaming {
+        chunk_size: 8,
+        upload_rate: Some(1024 * 1024),
+    });
+
+    let result = runner
+        .run_from_dir(
+            HttpMethod::Post,
+            None,
+            &"tests/test_requests".into(),
+            Order::Sequential,
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 3);
+    let mut len = 0;
+    for i in 1..=3 {
+        let content = fs::read_to_string(format!("tests/test_requests/test{i}.json"))
+            .await
+            .unwrap();
+        len += content.len() as u64;
+        let output = fs::read_to_string(format!("{dir}/success-{i}-test{i}.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["headers"]["transfer-encoding"], "chunked");
+        assert_eq!(body["body"], content);
+    }
+    assert_eq!(result.bytes_sent, len);
+    assert_eq!(result.uncompressed_bytes_sent, len);
+}
+
+#[tokio::test]
+async fn run_from_url_file_save_responses() {
+    let dir = "/tmp/load-rs/lib27";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/shop/", test_server.addr).as_str(),
+        5,
+        1,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_from_url_file(
+            HttpMethod::Post,
+            None,
+            Some(UrlEncoded(vec![("q".to_owned(), "red shoes".to_owned())])),
+            Path::new("tests/test_urls/urls1.txt"),
+            Order::Sequential,
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 5);
+    let paths = [
+        "/shop/products/1",
+        "/shop/products/2?color=red",
+        "/shop/cart",
+        "/shop/redirect?to=https://example.com/",
+        "/shop/products/1",
+    ];
+    for (i, path) in paths.iter().enumerate() {
+        let output = fs::read_to_string(format!("{dir}/success-{}.json", i + 1))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["method"], "POST");
+        assert_eq!(body["path"], *path);
+        assert_eq!(
+            body["headers"]["content-type"],
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(body["body"], "q=red+shoes");
+    }
+}
+
+#[tokio::test]
+async fn run_from_url_file_success_stats_average() {
+    let dir = Path::new("/tmp/load-rs/urls");
+    fs::create_dir_all(dir).await.unwrap();
+    let url_file = dir.join("mixed.txt");
+    fs::write(&url_file, "/ok\n/fail\n").await.unwrap();
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}", test_server.addr).as_str(),
+        6,
+        2,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_from_url_file(
+            HttpMethod::Get,
+            None,
+            None,
+            &url_file,
+            Order::Sequential,
+            &None,
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 3);
+    assert_eq!(result.failures, 3);
+    // The failures are not included in the statistics of the groups, so they do not count towards
+    // the average of a group.
+    assert_eq!(result.durations.len(), 3);
+    assert_eq!(result.avg, result.total_duration / 6);
+    let group = &result.groups["GET /ok"];
+    assert_eq!(group.avg, group.total_duration / 3);
+    let group = &result.groups["GET /fail"];
+    assert!(group.durations.is_empty());
+    assert_eq!(group.avg, Duration::default());
+}
+
+#[tokio::test]
+async fn run_from_dir_get_save_responses() {
+    let dir = "/tmp/load-rs/lib28";
+    let output_dir: PathBuf = dir.into();
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir).await.unwrap();
+    }
+    let test_server = run_echo_server().await.unwrap();
+
+    let runner = LoadTestRunner::new(
+        format!("http://{}/items", test_server.addr).as_str(),
+        3,
+        1,
+        Stats::Success,
+        &None,
+        &None,
+        &None,
+        &None,
+    )
+    .await
+    .unwrap();
+
+    let result = runner
+        .run_from_dir(
+            HttpMethod::Get,
+            None,
+            &"tests/test_requests".into(),
+            Order::Sequential,
+            &Some(output_dir),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.success, 3);
+    assert_eq!(result.bytes_sent, 0);
+    assert_eq!(
+        result.groups.keys().collect::<Vec<_>>(),
+        vec!["test1", "test2", "test3"]
+    );
+    for i in 1..=3 {
+        let output = fs::read_to_string(format!("{dir}/success-{i}-test{i}.json"))
+            .await
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(output["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["method"], "GET");
+        assert_eq!(body["path"], "/items");
+        assert_eq!(body["body"], "");
+    }
 }